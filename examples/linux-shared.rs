@@ -1,5 +1,9 @@
+// Reads two devices sharing a bus as one group. For a live view of every rail on a bus, run
+// `pac194x dashboard` from the `tui` feature instead.
+
 use embedded_hal_bus::i2c::RefCellDevice;
-use linux_embedded_hal::I2cdev;
+use linux_embedded_hal::{Delay, I2cdev};
+use pac194x::group::Group;
 use pac194x::{AddrSelect, PAC194X};
 use std::cell::RefCell;
 use std::{thread, time::Duration};
@@ -9,17 +13,19 @@ const SENSE_RESISTORS: [f32; 8] = [0.005, 0.010, 0.010, 0.010, 0.002, 0.010, 0.0
 fn main() {
     let i2c = RefCell::new(I2cdev::new("/dev/i2c-3").unwrap());
 
-    let bus_handle1 = RefCellDevice::new(&i2c);
-    let mut sensor1 = PAC194X::new(bus_handle1, AddrSelect::GND).unwrap();
-
-    let bus_handle2 = RefCellDevice::new(&i2c);
-    let mut sensor2 = PAC194X::new(bus_handle2, AddrSelect::_499).unwrap();
+    let sensor1 = PAC194X::new(RefCellDevice::new(&i2c), AddrSelect::GND).unwrap();
+    let sensor2 = PAC194X::new(RefCellDevice::new(&i2c), AddrSelect::_499).unwrap();
+    let mut group = Group::new([sensor1, sensor2]);
 
     loop {
-        print!("Sensor 1 ");
-        for channel in 1..5 {
-            let bus_voltage = sensor1.read_bus_voltage_n(channel).unwrap();
-            let sense_voltage = sensor1.read_sense_voltage_n(channel).unwrap();
+        // Both sensors are refreshed at the same instant with REFRESH_G
+        let m = group.snapshot(&mut Delay).unwrap();
+        for channel in 1..=m.channels() {
+            let (Some(bus_voltage), Some(sense_voltage)) =
+                (m.bus_voltage(channel), m.sense_voltage(channel))
+            else {
+                continue;
+            };
             print!(
                 "CH{} {:5.2}V, {:5.2}A, ",
                 channel,
@@ -28,25 +34,6 @@ fn main() {
             );
         }
         println!();
-        print!("Sensor 2 ");
-        for channel in 1..5 {
-            let bus_voltage = sensor2.read_bus_voltage_n(channel).unwrap();
-            let sense_voltage = sensor2.read_sense_voltage_n(channel).unwrap();
-            print!(
-                "CH{} {:5.2}V, {:5.2}A, ",
-                channel,
-                bus_voltage,
-                sense_voltage / SENSE_RESISTORS[(channel - 1 + 4) as usize]
-            );
-        }
-        println!();
-        println!();
-        sensor1.refresh().unwrap();
-        thread::sleep(Duration::from_millis(100));
-        sensor1.refresh_v().unwrap();
-        thread::sleep(Duration::from_millis(100));
-        sensor2.refresh().unwrap();
         thread::sleep(Duration::from_millis(100));
-        sensor2.refresh_v().unwrap();
     }
 }
@@ -0,0 +1,63 @@
+use i2cdev::core::I2CDevice;
+use i2cdev::linux::LinuxI2CDevice;
+use linux_embedded_hal::I2CError;
+use pac194x::smbus::{BLOCK_MAX, Smbus};
+use pac194x::{AddrSelect, PAC194X};
+use std::{thread, time::Duration};
+
+const SENSE_RESISTORS: [f32; 4] = [0.005, 0.010, 0.010, 0.010];
+
+/// An i2c-dev adapter that only supports SMBus transfers
+struct LinuxSmbus(LinuxI2CDevice);
+
+impl Smbus for LinuxSmbus {
+    type Error = I2CError;
+
+    fn send_byte(&mut self, address: u8, command: u8) -> Result<(), I2CError> {
+        self.0.set_slave_address(address as u16)?;
+        Ok(self.0.smbus_write_byte(command)?)
+    }
+
+    fn receive_byte(&mut self, address: u8) -> Result<u8, I2CError> {
+        self.0.set_slave_address(address as u16)?;
+        Ok(self.0.smbus_read_byte()?)
+    }
+
+    fn write_block(&mut self, address: u8, command: u8, data: &[u8]) -> Result<(), I2CError> {
+        self.0.set_slave_address(address as u16)?;
+        Ok(self.0.smbus_write_i2c_block_data(command, data)?)
+    }
+
+    fn read_block(
+        &mut self,
+        address: u8,
+        command: u8,
+        buf: &mut [u8; BLOCK_MAX],
+    ) -> Result<usize, I2CError> {
+        self.0.set_slave_address(address as u16)?;
+        let data = self.0.smbus_read_block_data(command)?;
+        buf[..data.len()].copy_from_slice(&data);
+        Ok(data.len())
+    }
+}
+
+fn main() {
+    let smbus = LinuxSmbus(LinuxI2CDevice::new("/dev/i2c-3", AddrSelect::GND as u16).unwrap());
+    let mut sensor = PAC194X::new_smbus(smbus, AddrSelect::GND).unwrap();
+    loop {
+        for channel in 1..5 {
+            let bus_voltage = sensor.read_bus_voltage_n(channel).unwrap();
+            let sense_voltage = sensor.read_sense_voltage_n(channel).unwrap();
+            print!(
+                "CH{} {:.2}V, {:.2}A, ",
+                channel,
+                bus_voltage,
+                sense_voltage / SENSE_RESISTORS[(channel - 1) as usize]
+            );
+        }
+        println!();
+
+        sensor.refresh().unwrap();
+        thread::sleep(Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,801 @@
+//! Waiting for ALERTs on the ALERT1 and ALERT2 pins
+//!
+//! When configured for the ALERT function in [`Ctrl`](crate::regs::Ctrl), the SLOW/ALERT1 and
+//! GPIO/ALERT2 pins are active-low outputs that stay asserted until [`AlertStatus`] is read, which clears
+//! it. [`PAC194X::wait_alert`] (with the `async` feature) awaits the pin through an `embedded-hal-async`
+//! `Wait` implementation such as an EXTI line, and [`PAC194X::poll_alert`] polls an [`InputPin`]. Both
+//! then read and clear ALERT_STATUS and return the [`Alerts`] it held.
+//!
+//! Because reading ALERT_STATUS clears it, an ALERT read by one task is lost to every other. An
+//! [`AlertLog`] keeps every read in a fixed-capacity queue until it is acknowledged, and counts how often
+//! each ALERT fired. Read ALERT_STATUS only through [`PAC194X::log_alerts`] (or record the result of the
+//! waiting functions with [`AlertLog::record`]) and let each consumer acknowledge what it handled.
+//!
+//! Which ALERTs assert which pin is described by an [`AlertRouting`], applied with
+//! [`PAC194X::route_alerts`], which also enables the routed ALERTs, and read back with
+//! [`PAC194X::read_alert_routing`]:
+//!
+//! ```
+//! use pac194x::alert::{AlertEvent, AlertPin, AlertRouting};
+//! use pac194x::regs::LimitAlert;
+//!
+//! // OC on channel 1 asserts ALERT1, UV on any channel asserts ALERT2
+//! let routing = AlertRouting::new()
+//!     .route(AlertEvent::Limit { alert: LimitAlert::Oc, channel: 1 }, AlertPin::Alert1)
+//!     .route_any_channel(LimitAlert::Uv, AlertPin::Alert2);
+//! let uv3 = AlertEvent::Limit { alert: LimitAlert::Uv, channel: 3 };
+//! assert!(routing.is_routed(uv3, AlertPin::Alert2));
+//! ```
+//!
+//! [`PAC194X::define_limit`] sets the threshold of a limit ALERT in physical units and how long it must be
+//! crossed before the ALERT triggers.
+
+use crate::regs::{AlertEnable, AlertStatus, GpioAlert, GpioAlert2, LimitAlert, SlowAlert1};
+use crate::timing::{self, Debounce};
+use crate::{Error, PAC194X, convert};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::digital::InputPin;
+use embedded_hal::i2c::I2c;
+use packed_struct::prelude::*;
+
+/// A single cause of an ALERT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertEvent {
+    /// A limit of a channel (1 to 4) was crossed
+    Limit { alert: LimitAlert, channel: u8 },
+    /// An accumulator overflowed or exceeded its limit in [`AccFullnessLimits`](crate::regs::AccFullnessLimits)
+    AccumulatorFull,
+    /// The accumulator count overflowed or exceeded its limit in
+    /// [`AccFullnessLimits`](crate::regs::AccFullnessLimits)
+    AccumulatorCountFull,
+}
+
+/// The ALERTs of one read of [`AlertStatus`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Alerts(pub AlertStatus);
+
+impl Alerts {
+    /// Whether no ALERT was set
+    pub fn is_empty(self) -> bool {
+        self.events().next().is_none()
+    }
+
+    /// The ALERTs that were set: the limits by kind and channel, then the accumulator ALERTs
+    pub fn events(self) -> impl Iterator<Item = AlertEvent> {
+        let status = self.0;
+        let limits = LimitAlert::ALL
+            .into_iter()
+            .flat_map(|alert| (1..=4).map(move |channel| AlertEvent::Limit { alert, channel }))
+            .filter(move |event| match event {
+                AlertEvent::Limit { alert, channel } => status.limit(*alert, *channel),
+                _ => false,
+            });
+        let accumulators = [
+            (status.acc_ovf, AlertEvent::AccumulatorFull),
+            (status.acc_count, AlertEvent::AccumulatorCountFull),
+        ]
+        .into_iter()
+        .filter_map(|(set, event)| set.then_some(event));
+        limits.chain(accumulators)
+    }
+}
+
+/// An ALERT_STATUS read kept by an [`AlertLog`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlertRecord {
+    /// Increments with every record, starting at 0
+    pub seq: u32,
+    /// When ALERT_STATUS was read, in the units of the caller's clock
+    pub timestamp: u64,
+    pub alerts: Alerts,
+}
+
+/// Number of counters: one per limit ALERT and channel, then the two accumulator ALERTs
+const COUNTERS: usize = LimitAlert::ALL.len() * 4 + 2;
+
+fn counter(event: AlertEvent) -> usize {
+    match event {
+        AlertEvent::Limit { alert, channel } => {
+            assert!(
+                (1..=4).contains(&channel),
+                "Channel n must be between 1 and 4"
+            );
+            alert as usize * 4 + (channel - 1) as usize
+        }
+        AlertEvent::AccumulatorFull => COUNTERS - 2,
+        AlertEvent::AccumulatorCountFull => COUNTERS - 1,
+    }
+}
+
+/// A queue of up to `N` unacknowledged ALERT_STATUS reads of one device, with counters of every ALERT.
+///
+/// When the queue is full, recording drops the oldest record and counts it in [`dropped`](Self::dropped).
+#[derive(Debug, Clone)]
+pub struct AlertLog<const N: usize> {
+    records: [Option<AlertRecord>; N],
+    next_seq: u32,
+    dropped: u32,
+    counts: [u32; COUNTERS],
+}
+
+impl<const N: usize> Default for AlertLog<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> AlertLog<N> {
+    pub const fn new() -> Self {
+        Self {
+            records: [None; N],
+            next_seq: 0,
+            dropped: 0,
+            counts: [0; COUNTERS],
+        }
+    }
+
+    /// Records the ALERTs read at `timestamp`, returning the sequence number of the record or `None` if
+    /// no ALERT was set
+    pub fn record(&mut self, timestamp: u64, alerts: Alerts) -> Option<u32> {
+        if alerts.is_empty() || N == 0 {
+            return None;
+        }
+        for event in alerts.events() {
+            let count = &mut self.counts[counter(event)];
+            *count = count.saturating_add(1);
+        }
+        let slot = match self.records.iter().position(Option::is_none) {
+            Some(free) => free,
+            None => {
+                self.dropped = self.dropped.saturating_add(1);
+                self.oldest_index().unwrap()
+            }
+        };
+        let seq = self.next_seq;
+        self.next_seq = self.next_seq.wrapping_add(1);
+        self.records[slot] = Some(AlertRecord {
+            seq,
+            timestamp,
+            alerts,
+        });
+        Some(seq)
+    }
+
+    /// Age of a record, so the order survives the sequence number wrapping around
+    fn age(&self, record: &AlertRecord) -> u32 {
+        self.next_seq.wrapping_sub(record.seq)
+    }
+
+    fn oldest_index(&self) -> Option<usize> {
+        (0..N)
+            .filter(|i| self.records[*i].is_some())
+            .max_by_key(|i| self.age(self.records[*i].as_ref().unwrap()))
+    }
+
+    /// The oldest unacknowledged record
+    pub fn oldest(&self) -> Option<&AlertRecord> {
+        self.records[self.oldest_index()?].as_ref()
+    }
+
+    /// The unacknowledged records, oldest first
+    pub fn pending(&self) -> impl Iterator<Item = &AlertRecord> {
+        let mut records: [Option<&AlertRecord>; N] =
+            core::array::from_fn(|i| self.records[i].as_ref());
+        records.sort_unstable_by_key(|r| r.map(|r| core::cmp::Reverse(self.age(r))));
+        records.into_iter().flatten()
+    }
+
+    /// The number of unacknowledged records
+    pub fn len(&self) -> usize {
+        self.records.iter().flatten().count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every ALERT of the unacknowledged records combined
+    pub fn latched(&self) -> Alerts {
+        let mut bytes = [0u8; 3];
+        for record in self.records.iter().flatten() {
+            for (latched, byte) in bytes.iter_mut().zip(record.alerts.0.pack().unwrap()) {
+                *latched |= byte;
+            }
+        }
+        Alerts(AlertStatus::unpack(&bytes).unwrap())
+    }
+
+    /// Acknowledges the record `seq`, removing it from the queue. Returns whether it was pending.
+    pub fn acknowledge(&mut self, seq: u32) -> bool {
+        match self
+            .records
+            .iter_mut()
+            .find(|r| r.is_some_and(|r| r.seq == seq))
+        {
+            Some(record) => {
+                *record = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Acknowledges every record
+    pub fn acknowledge_all(&mut self) {
+        self.records = [None; N];
+    }
+
+    /// How many times `event` was recorded, saturating at `u32::MAX`
+    pub fn count(&self, event: AlertEvent) -> u32 {
+        self.counts[counter(event)]
+    }
+
+    /// The number of records dropped unacknowledged because the queue was full
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+
+    /// Clears the counters and the dropped count
+    pub fn reset_counts(&mut self) {
+        self.counts = [0; COUNTERS];
+        self.dropped = 0;
+    }
+}
+
+/// The ALERT output pins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertPin {
+    /// SLOW/ALERT1
+    Alert1,
+    /// GPIO/ALERT2
+    Alert2,
+}
+
+/// Which ALERTs assert which pins.
+///
+/// A pin only asserts for the ALERTs routed to it, but every enabled ALERT is reported in ALERT_STATUS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlertRouting {
+    pub alert1: SlowAlert1,
+    pub alert2: GpioAlert2,
+}
+
+impl Default for AlertRouting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AlertRouting {
+    /// Nothing routed to either pin
+    pub fn new() -> Self {
+        Self {
+            alert1: SlowAlert1::unpack(&[0; 3]).unwrap(),
+            alert2: GpioAlert2::unpack(&[0; 3]).unwrap(),
+        }
+    }
+
+    /// Routes `event` to `pin`
+    pub fn route(mut self, event: AlertEvent, pin: AlertPin) -> Self {
+        match (event, pin) {
+            (AlertEvent::Limit { alert, channel }, AlertPin::Alert1) => {
+                self.alert1.set_limit(alert, channel, true)
+            }
+            (AlertEvent::Limit { alert, channel }, AlertPin::Alert2) => {
+                self.alert2.set_limit(alert, channel, true)
+            }
+            (AlertEvent::AccumulatorFull, AlertPin::Alert1) => self.alert1.acc_ovf = true,
+            (AlertEvent::AccumulatorFull, AlertPin::Alert2) => self.alert2.acc_ovf = true,
+            (AlertEvent::AccumulatorCountFull, AlertPin::Alert1) => self.alert1.acc_count = true,
+            (AlertEvent::AccumulatorCountFull, AlertPin::Alert2) => self.alert2.acc_count = true,
+        }
+        self
+    }
+
+    /// Routes `alert` of every channel to `pin`. Channels the part doesn't have are left out when applied.
+    pub fn route_any_channel(self, alert: LimitAlert, pin: AlertPin) -> Self {
+        (1..=4).fold(self, |routing, channel| {
+            routing.route(AlertEvent::Limit { alert, channel }, pin)
+        })
+    }
+
+    /// Asserts `pin` for 5 μs at the end of every conversion cycle
+    pub fn conversion_complete(mut self, pin: AlertPin) -> Self {
+        match pin {
+            AlertPin::Alert1 => self.alert1.alert_cc1 = true,
+            AlertPin::Alert2 => self.alert2.alert_cc2 = true,
+        }
+        self
+    }
+
+    /// Whether `event` asserts `pin`
+    pub fn is_routed(&self, event: AlertEvent, pin: AlertPin) -> bool {
+        match (event, pin) {
+            (AlertEvent::Limit { alert, channel }, AlertPin::Alert1) => {
+                self.alert1.limit(alert, channel)
+            }
+            (AlertEvent::Limit { alert, channel }, AlertPin::Alert2) => {
+                self.alert2.limit(alert, channel)
+            }
+            (AlertEvent::AccumulatorFull, AlertPin::Alert1) => self.alert1.acc_ovf,
+            (AlertEvent::AccumulatorFull, AlertPin::Alert2) => self.alert2.acc_ovf,
+            (AlertEvent::AccumulatorCountFull, AlertPin::Alert1) => self.alert1.acc_count,
+            (AlertEvent::AccumulatorCountFull, AlertPin::Alert2) => self.alert2.acc_count,
+        }
+    }
+
+    /// Whether `pin` asserts at the end of every conversion cycle
+    pub fn is_conversion_complete(&self, pin: AlertPin) -> bool {
+        match pin {
+            AlertPin::Alert1 => self.alert1.alert_cc1,
+            AlertPin::Alert2 => self.alert2.alert_cc2,
+        }
+    }
+
+    /// Whether anything is routed to `pin`
+    pub fn uses(&self, pin: AlertPin) -> bool {
+        let bytes = match pin {
+            AlertPin::Alert1 => self.alert1.pack().unwrap(),
+            AlertPin::Alert2 => self.alert2.pack().unwrap(),
+        };
+        bytes.iter().any(|b| *b != 0)
+    }
+
+    /// The ALERTs that must be enabled for the routing to work
+    fn enables(&self) -> [u8; 3] {
+        let (alert1, alert2) = (self.alert1.pack().unwrap(), self.alert2.pack().unwrap());
+        // The three registers share their layout
+        core::array::from_fn(|i| alert1[i] | alert2[i])
+    }
+}
+
+/// The threshold of a limit ALERT in physical units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LimitThreshold {
+    /// Overvoltage, a bus voltage in microvolts
+    Ov(i64),
+    /// Undervoltage, a bus voltage in microvolts
+    Uv(i64),
+    /// Overcurrent, a current in microamps through a sense resistor of `shunt_uohm` micro-ohms
+    Oc { ua: i64, shunt_uohm: u32 },
+    /// Undercurrent, a current in microamps through a sense resistor of `shunt_uohm` micro-ohms
+    Uc { ua: i64, shunt_uohm: u32 },
+    /// Overpower, a power in microwatts through a sense resistor of `shunt_uohm` micro-ohms
+    Op { uw: i64, shunt_uohm: u32 },
+}
+
+impl LimitThreshold {
+    /// The ALERT the threshold is for
+    pub fn alert(&self) -> LimitAlert {
+        match self {
+            Self::Ov(_) => LimitAlert::Ov,
+            Self::Uv(_) => LimitAlert::Uv,
+            Self::Oc { .. } => LimitAlert::Oc,
+            Self::Uc { .. } => LimitAlert::Uc,
+            Self::Op { .. } => LimitAlert::Op,
+        }
+    }
+}
+
+/// Errors while waiting for an ALERT
+#[derive(Debug, PartialEq)]
+pub enum WaitError<E, P> {
+    /// Reading ALERT_STATUS failed
+    Device(Error<E>),
+    /// Reading the ALERT pin failed
+    Pin(P),
+}
+
+impl<E, P> From<Error<E>> for WaitError<E, P> {
+    fn from(e: Error<E>) -> Self {
+        Self::Device(e)
+    }
+}
+
+impl<E, I> PAC194X<I>
+where
+    I: I2c<Error = E>,
+{
+    /// Reads and clears ALERT_STATUS, deasserting the ALERT pins
+    pub fn read_alerts(&mut self) -> Result<Alerts, Error<E>> {
+        Ok(Alerts(self.read_alert_statuc()?))
+    }
+
+    /// Reads and clears ALERT_STATUS and records it in `log` at `timestamp`
+    pub fn log_alerts<const N: usize>(
+        &mut self,
+        log: &mut AlertLog<N>,
+        timestamp: u64,
+    ) -> Result<Alerts, Error<E>> {
+        let alerts = self.read_alerts()?;
+        log.record(timestamp, alerts);
+        Ok(alerts)
+    }
+
+    /// Routes the ALERTs to the pins as described by `routing` and enables them in [`AlertEnable`], then
+    /// activates the change with REFRESH_V, which keeps the accumulators.
+    ///
+    /// ALERTs that were already enabled stay enabled. Returns [`Error::AlertPinMode`] if a pin with ALERTs
+    /// routed to it isn't configured for [`GpioAlert::Alert`] in [`CtrlAct`](crate::regs::CtrlAct) and
+    /// [`Error::NoSuchChannel`] if a single channel is routed that the part doesn't have. Nothing is
+    /// written when the routing is rejected.
+    pub fn route_alerts(&mut self, routing: &AlertRouting) -> Result<(), Error<E>> {
+        let channels = self.product_id.channels();
+        let mut routing = *routing;
+        for alert in LimitAlert::ALL {
+            for pin in [AlertPin::Alert1, AlertPin::Alert2] {
+                let routed = |n| routing.is_routed(AlertEvent::Limit { alert, channel: n }, pin);
+                let every_channel = (1..=4).all(routed);
+                if !every_channel && let Some(n) = (channels + 1..=4).find(|n| routed(*n)) {
+                    return Err(Error::NoSuchChannel(n));
+                }
+                if every_channel {
+                    // Leave out the channels the part doesn't have
+                    for n in channels + 1..=4 {
+                        match pin {
+                            AlertPin::Alert1 => routing.alert1.set_limit(alert, n, false),
+                            AlertPin::Alert2 => routing.alert2.set_limit(alert, n, false),
+                        }
+                    }
+                }
+            }
+        }
+        let ctrl = self.read_ctrl_act()?;
+        for (pin, function) in [
+            (AlertPin::Alert1, ctrl.slow_alert1),
+            (AlertPin::Alert2, ctrl.gpio_alert2),
+        ] {
+            if routing.uses(pin) && function != GpioAlert::Alert {
+                return Err(Error::AlertPinMode(pin));
+            }
+        }
+
+        let mut enable = self.read_alert_enable()?.pack().unwrap();
+        for (enabled, routed) in enable.iter_mut().zip(routing.enables()) {
+            *enabled |= routed;
+        }
+        self.write_alert_enable(AlertEnable::unpack(&enable).unwrap())?;
+        self.write_slow_alert1(routing.alert1)?;
+        self.write_gpio_alert2(routing.alert2)?;
+        self.refresh_v()
+    }
+
+    /// Sets the limit ALERT of `channel` (1 to 4) to `threshold`, triggered after it has been crossed for
+    /// about `debounce_us` microseconds, and enables it
+    ///
+    /// The debounce is the [`SampleCount`](crate::regs::SampleCount) of consecutive samples nearest to
+    /// `debounce_us` at the sample rate of the active settings, see [`timing::debounce`]; the time it
+    /// achieves is returned. The threshold is converted with the active full scale ranges of the channel.
+    /// Returns [`Error::NoSampleRate`] in a sample mode without a fixed rate and
+    /// [`Error::LimitOutOfRange`] if the threshold is outside of the full scale range. The ALERT is disabled
+    /// with a REFRESH_V while its limit changes to avoid false triggers and enabled again with another.
+    pub fn define_limit(
+        &mut self,
+        channel: u8,
+        threshold: LimitThreshold,
+        debounce_us: u32,
+    ) -> Result<Debounce, Error<E>> {
+        if !(1..=self.product_id.channels()).contains(&channel) {
+            return Err(Error::NoSuchChannel(channel));
+        }
+        let debounce = timing::debounce(debounce_us, self.read_timing()?.rate()?);
+        let max_uv = self.product_id.max_voltage_uv();
+        let fsr = self.read_neg_pwr_fsr_act()?;
+        let (vbus_fsr, vsense_fsr) = (fsr.vbus_fsr(channel), fsr.vsense_fsr(channel));
+        // OV, UV, OC and UC limits are 16 bits, OP limits 24
+        let limit = match threshold {
+            LimitThreshold::Ov(uv) | LimitThreshold::Uv(uv) => {
+                convert::uv_to_vbus_limit(uv, max_uv, vbus_fsr).map(i32::from)
+            }
+            LimitThreshold::Oc { ua, shunt_uohm } | LimitThreshold::Uc { ua, shunt_uohm } => {
+                convert::ua_to_vsense_limit(ua, vsense_fsr, shunt_uohm).map(i32::from)
+            }
+            LimitThreshold::Op { uw, shunt_uohm } => {
+                convert::uw_to_power_limit(uw, max_uv, vbus_fsr, vsense_fsr, shunt_uohm)
+            }
+        }
+        .ok_or(Error::LimitOutOfRange)?;
+
+        let alert = threshold.alert();
+        let mut enable = self.read_alert_enable()?;
+        enable.set_limit(alert, channel, false);
+        self.write_alert_enable(enable)?;
+        self.refresh_v()?;
+        let (n, count) = (channel, debounce.count);
+        match alert {
+            LimitAlert::Ov => {
+                self.modify_ov_limitn(n, |l| l.limit = limit as i16)?;
+                self.modify_ov_limit_n_samples(|r| r.set_n_samples(n, count))?;
+            }
+            LimitAlert::Uv => {
+                self.modify_uv_limitn(n, |l| l.limit = limit as i16)?;
+                self.modify_uv_limit_n_samples(|r| r.set_n_samples(n, count))?;
+            }
+            LimitAlert::Oc => {
+                self.modify_oc_limitn(n, |l| l.limit = limit as i16)?;
+                self.modify_oc_limit_n_samples(|r| r.set_n_samples(n, count))?;
+            }
+            LimitAlert::Uc => {
+                self.modify_uc_limitn(n, |l| l.limit = limit as i16)?;
+                self.modify_uc_limit_n_samples(|r| r.set_n_samples(n, count))?;
+            }
+            LimitAlert::Op => {
+                self.modify_op_limitn(n, |l| l.limit = limit)?;
+                self.modify_op_limit_n_samples(|r| r.set_n_samples(n, count))?;
+            }
+        }
+        enable.set_limit(alert, channel, true);
+        self.write_alert_enable(enable)?;
+        self.refresh_v()?;
+        Ok(debounce)
+    }
+
+    /// Reads which ALERTs are routed to which pins
+    pub fn read_alert_routing(&mut self) -> Result<AlertRouting, Error<E>> {
+        Ok(AlertRouting {
+            alert1: self.read_slow_alert1()?,
+            alert2: self.read_gpio_alert2()?,
+        })
+    }
+
+    /// Polls the active-low ALERT `pin` every `interval_us` microseconds until it is asserted, then reads
+    /// and clears ALERT_STATUS
+    pub fn poll_alert<P, D>(
+        &mut self,
+        pin: &mut P,
+        delay: &mut D,
+        interval_us: u32,
+    ) -> Result<Alerts, WaitError<E, P::Error>>
+    where
+        P: InputPin,
+        D: DelayNs,
+    {
+        while pin.is_high().map_err(WaitError::Pin)? {
+            delay.delay_us(interval_us);
+        }
+        Ok(self.read_alerts()?)
+    }
+
+    /// Waits for the falling edge of the active-low ALERT `pin`, then reads and clears ALERT_STATUS.
+    ///
+    /// An ALERT asserted before the call holds the pin low without another edge, so clear ALERT_STATUS
+    /// with [`read_alerts`](Self::read_alerts) or [`log_alerts`](Self::log_alerts) before waiting.
+    #[cfg(feature = "async")]
+    pub async fn wait_alert<P>(&mut self, pin: &mut P) -> Result<Alerts, WaitError<E, P::Error>>
+    where
+        P: embedded_hal_async::digital::Wait,
+    {
+        pin.wait_for_falling_edge().await.map_err(WaitError::Pin)?;
+        Ok(self.read_alerts()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AddrSelect;
+    use crate::regs::{Address, SampleCount, SampleMode, VBusFSR};
+    use crate::sim::SimBus;
+    use embedded_hal_mock::eh1::delay::NoopDelay;
+    use embedded_hal_mock::eh1::digital::{Mock, State, Transaction};
+    use std::vec::Vec;
+
+    fn sensor_with_alerts() -> PAC194X<SimBus> {
+        let bus = SimBus::new(&[0x10]);
+        // OC on channel 2, UV on channel 4 and the accumulator count
+        bus.with(0x10, |dev| {
+            dev.set(
+                Address::AlertStatus,
+                &[0b0100_0000, 0b0000_0001, 0b0000_0100],
+            )
+        });
+        PAC194X::new(bus, AddrSelect::GND).unwrap()
+    }
+
+    const EVENTS: [AlertEvent; 3] = [
+        AlertEvent::Limit {
+            alert: LimitAlert::Oc,
+            channel: 2,
+        },
+        AlertEvent::Limit {
+            alert: LimitAlert::Uv,
+            channel: 4,
+        },
+        AlertEvent::AccumulatorCountFull,
+    ];
+
+    #[test]
+    fn poll_until_asserted() {
+        let mut sensor = sensor_with_alerts();
+        let mut pin = Mock::new(&[
+            Transaction::get(State::High),
+            Transaction::get(State::High),
+            Transaction::get(State::Low),
+        ]);
+        let alerts = sensor.poll_alert(&mut pin, &mut NoopDelay, 100).unwrap();
+        assert_eq!(alerts.events().collect::<Vec<_>>(), EVENTS);
+        // Reading ALERT_STATUS cleared it
+        assert!(sensor.read_alerts().unwrap().is_empty());
+        pin.done();
+    }
+
+    #[test]
+    fn log_until_acknowledged() {
+        let mut sensor = sensor_with_alerts();
+        let mut log = AlertLog::<2>::new();
+        assert_eq!(sensor.log_alerts(&mut log, 10).unwrap().events().count(), 3);
+        // Nothing new, nothing recorded
+        sensor.log_alerts(&mut log, 20).unwrap();
+        assert_eq!(log.len(), 1);
+
+        let oc = |channel| {
+            let mut status = AlertStatus::unpack(&[0; 3]).unwrap();
+            status.set_limit(LimitAlert::Oc, channel, true);
+            Alerts(status)
+        };
+        assert_eq!(log.record(30, oc(1)), Some(1));
+        assert_eq!(log.record(40, oc(2)), Some(2));
+        assert_eq!(log.dropped(), 1);
+        let pending: Vec<_> = log.pending().map(|r| (r.seq, r.timestamp)).collect();
+        assert_eq!(pending, [(1, 30), (2, 40)]);
+        assert_eq!(
+            log.latched().events().collect::<Vec<_>>(),
+            [
+                AlertEvent::Limit {
+                    alert: LimitAlert::Oc,
+                    channel: 1
+                },
+                EVENTS[0],
+            ]
+        );
+
+        assert!(log.acknowledge(1));
+        assert!(!log.acknowledge(1));
+        assert_eq!(log.oldest().unwrap().seq, 2);
+        assert_eq!(log.count(EVENTS[0]), 2);
+        assert_eq!(log.count(EVENTS[2]), 1);
+        log.acknowledge_all();
+        assert!(log.is_empty());
+    }
+
+    #[test]
+    fn route_to_pins() {
+        let bus = SimBus::new(&[0x10]);
+        let mut sensor =
+            PAC194X::new_with_product_id(bus.clone(), AddrSelect::GND, crate::ProductId::PAC1942_1);
+        let oc1 = AlertEvent::Limit {
+            alert: LimitAlert::Oc,
+            channel: 1,
+        };
+        let routing = AlertRouting::new()
+            .route(oc1, AlertPin::Alert1)
+            .route_any_channel(LimitAlert::Uv, AlertPin::Alert2);
+
+        // The POR pin functions are SLOW and GPIO input
+        bus.with(0x10, |dev| {
+            dev.set(Address::Ctrl, &[0x07, 0x00]);
+            dev.set(Address::CtrlAct, &[0x07, 0x00]);
+        });
+        assert_eq!(
+            sensor.route_alerts(&routing),
+            Err(Error::AlertPinMode(AlertPin::Alert1))
+        );
+        sensor
+            .modify_ctrl(|ctrl| {
+                ctrl.slow_alert1 = GpioAlert::Alert;
+                ctrl.gpio_alert2 = GpioAlert::Alert;
+            })
+            .unwrap();
+        // The pin functions only change at the next REFRESH
+        assert_eq!(
+            sensor.route_alerts(&routing),
+            Err(Error::AlertPinMode(AlertPin::Alert1))
+        );
+        sensor.refresh_v().unwrap();
+        // The PAC1942 has two channels
+        let ov3 = AlertEvent::Limit {
+            alert: LimitAlert::Ov,
+            channel: 3,
+        };
+        assert_eq!(
+            sensor.route_alerts(&routing.route(ov3, AlertPin::Alert1)),
+            Err(Error::NoSuchChannel(3))
+        );
+
+        sensor.route_alerts(&routing).unwrap();
+        let applied = sensor.read_alert_routing().unwrap();
+        assert!(applied.is_routed(oc1, AlertPin::Alert1));
+        assert!(!applied.is_routed(oc1, AlertPin::Alert2));
+        let uv = |channel| AlertEvent::Limit {
+            alert: LimitAlert::Uv,
+            channel,
+        };
+        assert!(applied.is_routed(uv(2), AlertPin::Alert2));
+        assert!(!applied.is_routed(uv(3), AlertPin::Alert2));
+        let enable = sensor.read_alert_enable().unwrap();
+        assert!(enable.ch1_oc && enable.ch1_uv && enable.ch2_uv);
+        assert!(!enable.ch2_oc && !enable.ch3_uv);
+    }
+
+    #[test]
+    fn define_debounced_limit() {
+        let bus = SimBus::new(&[0x10]);
+        let mut sensor = PAC194X::new(bus, AddrSelect::GND).unwrap();
+        // 4 samples at the default 1024 SPS are nearest to 5 ms
+        let debounce = sensor
+            .define_limit(2, LimitThreshold::Ov(5_000_000), 5_000)
+            .unwrap();
+        assert_eq!(debounce.count, SampleCount::_4);
+        assert_eq!(debounce.effective_us, 3906);
+        let max_uv = sensor.product_id.max_voltage_uv();
+        assert_eq!(
+            sensor.read_ov_limitn(2).unwrap().limit,
+            convert::uv_to_vbus_limit(5_000_000, max_uv, VBusFSR::Unipolar).unwrap()
+        );
+        let n_samples = sensor.read_ov_limit_n_samples().unwrap();
+        assert_eq!(n_samples.n_samples(2), SampleCount::_4);
+        assert_eq!(n_samples.n_samples(1), SampleCount::_1);
+        assert!(sensor.read_alert_enable().unwrap().ch2_ov);
+        // Redefined while the OV ALERT of channel 2 is active, which must be deactivated first
+        sensor
+            .define_limit(2, LimitThreshold::Ov(6_000_000), 0)
+            .unwrap();
+        sensor.i2c.with(0x10, |dev| {
+            let writes = dev.limit_writes();
+            assert_eq!(writes.len(), 4);
+            for (_, enable) in writes {
+                assert!(!AlertEnable::unpack_from_slice(enable).unwrap().ch2_ov);
+            }
+        });
+        // A range activated by the last REFRESH is only latched by the next one
+        sensor
+            .modify_neg_pwr_fsr(|fsr| fsr.set_vbus_fsr(3, VBusFSR::BipolarLV))
+            .unwrap();
+        sensor.refresh_v().unwrap();
+        sensor
+            .define_limit(3, LimitThreshold::Ov(3_000_000), 0)
+            .unwrap();
+        assert_eq!(
+            sensor.read_ov_limitn(3).unwrap().limit,
+            convert::uv_to_vbus_limit(3_000_000, max_uv, VBusFSR::BipolarLV).unwrap()
+        );
+
+        // 100 A through 10 mΩ is beyond the 100 mV VSENSE range
+        let oc = LimitThreshold::Oc {
+            ua: 100_000_000,
+            shunt_uohm: 10_000,
+        };
+        assert_eq!(sensor.define_limit(1, oc, 0), Err(Error::LimitOutOfRange));
+        assert_eq!(
+            sensor.define_limit(5, LimitThreshold::Uv(0), 0),
+            Err(Error::NoSuchChannel(5))
+        );
+        sensor
+            .batch(|s| s.modify_ctrl(|ctrl| ctrl.sample_mode = SampleMode::Sleep))
+            .unwrap();
+        assert_eq!(
+            sensor.define_limit(1, LimitThreshold::Uv(0), 0),
+            Err(Error::NoSampleRate(SampleMode::Sleep))
+        );
+    }
+
+    #[cfg(feature = "async")]
+    #[test]
+    fn wait_for_pin() {
+        use core::pin::pin;
+        use core::task::{Context, Poll, Waker};
+        use embedded_hal_mock::eh1::digital::Edge;
+
+        let mut sensor = sensor_with_alerts();
+        let mut pin = Mock::new(&[Transaction::wait_for_edge(Edge::Falling)]);
+        let alerts = {
+            let wait = pin!(sensor.wait_alert(&mut pin));
+            let Poll::Ready(alerts) = wait.poll(&mut Context::from_waker(Waker::noop())) else {
+                panic!("the mock pin is ready right away");
+            };
+            alerts.unwrap()
+        };
+        assert_eq!(alerts.events().collect::<Vec<_>>(), EVENTS);
+        pin.done();
+    }
+}
@@ -0,0 +1,432 @@
+//! Live dashboard of every channel of every device on the bus
+
+use crate::{Cli, Failure, Mode, micro, open};
+use clap::ValueEnum;
+use embedded_hal_bus::i2c::RefCellDevice;
+use linux_embedded_hal::I2cdev;
+use pac194x::config_file::ConfigFile;
+use pac194x::regs::{LimitAlert, SampleMode};
+use pac194x::{Error, PAC194X, ProductId, scan};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Paragraph, Row, Sparkline, Table, TableState};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Samples kept for the rolling minimum and maximum and the sparklines
+const HISTORY: usize = 120;
+
+const HELP: &str = "q quit  ↑↓ select  r refresh  a reset accumulators  t toggle channel  m/M sample mode  c clear min/max  p pause";
+
+type Sensor<'a> = PAC194X<RefCellDevice<'a, I2cdev>>;
+
+/// How to run the dashboard, from the command line
+pub struct Options<'a> {
+    pub config: Option<&'a PathBuf>,
+    pub shunt: Option<f64>,
+    pub interval: f64,
+}
+
+struct Device<'a> {
+    sensor: Sensor<'a>,
+    product_id: ProductId,
+    sample_mode: SampleMode,
+}
+
+/// One sample of a channel, in microvolts, microamps and microwatts
+#[derive(Clone, Copy)]
+struct Sample {
+    bus_uv: i32,
+    current_ua: Option<i64>,
+    power_uw: Option<i64>,
+}
+
+/// A channel of a device, one row of the table
+struct Channel {
+    device: usize,
+    n: u8,
+    rail: Option<String>,
+    shunt_uohm: Option<u32>,
+    /// `None` while the channel is disabled
+    latest: Option<Sample>,
+    alerts: Vec<LimitAlert>,
+    history: VecDeque<Sample>,
+}
+
+impl Channel {
+    /// The range of a value over the history
+    fn range(&self, value: impl Fn(&Sample) -> Option<i64>) -> Option<(i64, i64)> {
+        let values = self.history.iter().filter_map(&value);
+        let min = values.min()?;
+        Some((min, self.history.iter().filter_map(value).max()?))
+    }
+
+    /// The sparkline values: power if the sense resistor is known, otherwise the bus voltage, above the
+    /// minimum of the history so small variations are visible
+    fn sparkline(&self) -> Vec<u64> {
+        let value = |s: &Sample| s.power_uw.unwrap_or(s.bus_uv as i64);
+        let min = self.history.iter().map(value).min().unwrap_or_default();
+        self.history
+            .iter()
+            .map(|s| (value(s) - min) as u64)
+            .collect()
+    }
+}
+
+struct Dashboard<'a> {
+    devices: Vec<Device<'a>>,
+    channels: Vec<Channel>,
+    table: TableState,
+    paused: bool,
+    status: String,
+}
+
+/// The name of a sample mode on the command line
+fn mode_name(mode: SampleMode) -> String {
+    Mode::value_variants()
+        .iter()
+        .find(|m| SampleMode::from(**m) == mode)
+        .and_then(|m| m.to_possible_value())
+        .map_or_else(|| format!("{mode:?}"), |v| v.get_name().to_string())
+}
+
+/// Formats millionths with `decimals` decimals
+fn unit(micro: Option<i64>, decimals: usize) -> String {
+    micro.map_or_else(String::new, |m| format!("{:.decimals$}", m as f64 / 1e6))
+}
+
+impl Dashboard<'_> {
+    fn selected(&self) -> Option<&Channel> {
+        self.channels.get(self.table.selected()?)
+    }
+
+    /// Refreshes every device with REFRESH_V and reads the results and ALERT_STATUS
+    fn sample(&mut self) -> Result<(), Error<linux_embedded_hal::I2CError>> {
+        for device in &mut self.devices {
+            device.sensor.refresh_v()?;
+        }
+        thread::sleep(Duration::from_millis(1));
+        for (i, device) in self.devices.iter_mut().enumerate() {
+            let m = device.sensor.read_measurements()?;
+            // Reading ALERT_STATUS clears it, so each sample shows the ALERTs since the previous one
+            let status = device.sensor.read_alert_statuc()?;
+            for channel in self.channels.iter_mut().filter(|c| c.device == i) {
+                let n = channel.n;
+                channel.alerts = LimitAlert::ALL
+                    .into_iter()
+                    .filter(|a| status.limit(*a, n))
+                    .collect();
+                channel.latest = m.bus_voltage_uv(n).map(|bus_uv| Sample {
+                    bus_uv,
+                    current_ua: channel.shunt_uohm.and_then(|r| m.current_ua(n, r)),
+                    power_uw: channel.shunt_uohm.and_then(|r| m.power_uw(n, r)),
+                });
+                if let Some(sample) = channel.latest {
+                    if channel.history.len() == HISTORY {
+                        channel.history.pop_front();
+                    }
+                    channel.history.push_back(sample);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Samples and reports a failure in the status line instead of quitting
+    fn update(&mut self) {
+        if let Err(e) = self.sample() {
+            self.status = Failure::from(e).0;
+        }
+    }
+
+    /// Resets the accumulators of every device with REFRESH
+    fn reset_accumulators(&mut self) -> Result<(), Error<linux_embedded_hal::I2CError>> {
+        for device in &mut self.devices {
+            device.sensor.refresh()?;
+        }
+        self.status = "Accumulators reset".into();
+        Ok(())
+    }
+
+    /// Enables or disables the selected channel, which takes a REFRESH and so resets the accumulators
+    fn toggle_channel(&mut self) -> Result<(), Error<linux_embedded_hal::I2CError>> {
+        let Some(&Channel { device, n, .. }) = self.selected() else {
+            return Ok(());
+        };
+        let sensor = &mut self.devices[device].sensor;
+        let off = !sensor.read_ctrl()?.channel_n_off.get(n);
+        sensor.batch(|s| s.modify_ctrl(|ctrl| ctrl.channel_n_off.set(n, off)))?;
+        self.status = format!(
+            "{:#04x} CH{n} {}",
+            sensor.address(),
+            if off { "disabled" } else { "enabled" }
+        );
+        Ok(())
+    }
+
+    /// Moves the device of the selected channel `step` sample modes along the list
+    fn change_sample_mode(
+        &mut self,
+        step: isize,
+    ) -> Result<(), Error<linux_embedded_hal::I2CError>> {
+        let Some(&Channel { device, .. }) = self.selected() else {
+            return Ok(());
+        };
+        let device = &mut self.devices[device];
+        let modes = Mode::value_variants();
+        let current = modes
+            .iter()
+            .position(|m| SampleMode::from(*m) == device.sample_mode)
+            .unwrap_or_default();
+        let mode =
+            modes[(current as isize + step).rem_euclid(modes.len() as isize) as usize].into();
+        device
+            .sensor
+            .batch(|s| s.modify_ctrl(|ctrl| ctrl.sample_mode = mode))?;
+        device.sample_mode = mode;
+        self.status = format!(
+            "{:#04x} sample mode {}",
+            device.sensor.address(),
+            mode_name(mode)
+        );
+        Ok(())
+    }
+
+    /// Handles a key, returning whether to quit
+    fn key(&mut self, code: KeyCode) -> bool {
+        let result = match code {
+            KeyCode::Char('q') | KeyCode::Esc => return true,
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.table.select_previous();
+                Ok(())
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.table.select_next();
+                Ok(())
+            }
+            KeyCode::Char('r') => self.sample(),
+            KeyCode::Char('a') => self.reset_accumulators(),
+            KeyCode::Char('t') => self.toggle_channel(),
+            KeyCode::Char('m') => self.change_sample_mode(1),
+            KeyCode::Char('M') => self.change_sample_mode(-1),
+            KeyCode::Char('c') => {
+                self.channels.iter_mut().for_each(|c| c.history.clear());
+                Ok(())
+            }
+            KeyCode::Char('p') => {
+                self.paused = !self.paused;
+                self.status = if self.paused { "Paused" } else { "" }.into();
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            self.status = Failure::from(e).0;
+        }
+        false
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [devices, body, footer] = Layout::vertical([
+            Constraint::Length(self.devices.len() as u16),
+            Constraint::Fill(1),
+            Constraint::Length(2),
+        ])
+        .areas(frame.area());
+
+        let lines: Vec<Line> = self
+            .devices
+            .iter()
+            .map(|d| {
+                Line::from(format!(
+                    "{:#04x} {} sample mode {}",
+                    d.sensor.address(),
+                    d.product_id.name(),
+                    mode_name(d.sample_mode)
+                ))
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), devices);
+
+        let header = Row::new([
+            "Device", "CH", "Rail", "V", "V min", "V max", "A", "A min", "A max", "W", "Alerts",
+        ])
+        .style(Style::new().bold());
+        let rows = self.channels.iter().map(|c| {
+            let address = format!("{:#04x}", self.devices[c.device].sensor.address());
+            let rail = c.rail.clone().unwrap_or_default();
+            let Some(latest) = c.latest else {
+                return Row::new([address, c.n.to_string(), rail, "off".into()])
+                    .style(Style::new().dim());
+            };
+            let voltage = c.range(|s| Some(s.bus_uv as i64));
+            let current = c.range(|s| s.current_ua);
+            let alerts: Vec<String> = c
+                .alerts
+                .iter()
+                .map(|a| format!("{a:?}").to_uppercase())
+                .collect();
+            let row = Row::new([
+                address,
+                c.n.to_string(),
+                rail,
+                unit(Some(latest.bus_uv as i64), 3),
+                unit(voltage.map(|r| r.0), 3),
+                unit(voltage.map(|r| r.1), 3),
+                unit(latest.current_ua, 4),
+                unit(current.map(|r| r.0), 4),
+                unit(current.map(|r| r.1), 4),
+                unit(latest.power_uw, 4),
+                alerts.join(" "),
+            ]);
+            if c.alerts.is_empty() {
+                row
+            } else {
+                row.style(Style::new().red())
+            }
+        });
+        let widths = [
+            Constraint::Length(6),
+            Constraint::Length(2),
+            Constraint::Length(12),
+            Constraint::Length(7),
+            Constraint::Length(7),
+            Constraint::Length(7),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Length(14),
+        ];
+        let table_width = widths.iter().map(|w| match w {
+            Constraint::Length(l) => l + 1,
+            _ => 0,
+        });
+        let [table_area, sparkline_area] = Layout::horizontal([
+            Constraint::Length(table_width.sum::<u16>() + 3),
+            Constraint::Fill(1),
+        ])
+        .areas(body);
+        let table = Table::new(rows, widths)
+            .header(header)
+            .row_highlight_style(Style::new().reversed())
+            .highlight_symbol("> ")
+            .block(Block::bordered().title(" Channels "));
+        frame.render_stateful_widget(table, table_area, &mut self.table);
+
+        // One sparkline per visible row, level with it
+        let block = Block::bordered().title(" History ");
+        let inner = block.inner(sparkline_area);
+        frame.render_widget(block, sparkline_area);
+        let first = self.table.offset();
+        for (row, channel) in self.channels.iter().skip(first).enumerate() {
+            let y = inner.y + 1 + row as u16;
+            if y >= inner.bottom() {
+                break;
+            }
+            let area = Rect::new(inner.x, y, inner.width, 1);
+            let data = channel.sparkline();
+            // Show the newest samples when the history is wider than the area
+            let start = data.len().saturating_sub(area.width as usize);
+            frame.render_widget(Sparkline::default().data(&data[start..]), area);
+        }
+
+        frame.render_widget(
+            Paragraph::new(vec![
+                Line::from(self.status.as_str()),
+                Line::from(HELP).dim(),
+            ]),
+            footer,
+        );
+    }
+}
+
+pub fn dashboard(cli: &Cli, options: &Options) -> Result<(), Failure> {
+    let board = options
+        .config
+        .map(ConfigFile::load)
+        .transpose()?
+        .map(|c| c.board)
+        .unwrap_or_default();
+    let interval = Duration::try_from_secs_f64(options.interval)
+        .ok()
+        .filter(|i| !i.is_zero())
+        .ok_or_else(|| Failure("--interval must be a positive number of seconds".into()))?;
+
+    let i2c = RefCell::new(open(&cli.bus)?);
+    let scan = scan::scan(&mut *i2c.borrow_mut())?;
+    let mut devices = Vec::new();
+    let mut channels = Vec::new();
+    for found in scan.devices() {
+        let mut sensor = PAC194X::new_with_product_id(
+            RefCellDevice::new(&i2c),
+            found.addr_sel,
+            found.product_id,
+        );
+        let sample_mode = sensor.read_ctrl()?.sample_mode;
+        for n in 1..=found.channels() {
+            let rail = board
+                .rails
+                .iter()
+                .find(|r| r.addr_sel == found.addr_sel && r.channel == n);
+            channels.push(Channel {
+                device: devices.len(),
+                n,
+                rail: rail.map(|r| r.name.clone()),
+                shunt_uohm: rail
+                    .map(|r| r.shunt_uohm)
+                    .or(options.shunt.map(|r| micro(r) as u32))
+                    .filter(|r| *r > 0),
+                latest: None,
+                alerts: Vec::new(),
+                history: VecDeque::with_capacity(HISTORY),
+            });
+        }
+        devices.push(Device {
+            sensor,
+            product_id: found.product_id,
+            sample_mode,
+        });
+    }
+    if devices.is_empty() {
+        return Err(Failure(format!("no devices found on {}", cli.bus)));
+    }
+
+    let mut dashboard = Dashboard {
+        devices,
+        channels,
+        table: TableState::new().with_selected(Some(0)),
+        paused: false,
+        status: String::new(),
+    };
+    let mut terminal = ratatui::init();
+    let mut next = Instant::now();
+    let result = loop {
+        if !dashboard.paused && Instant::now() >= next {
+            dashboard.update();
+            next = Instant::now() + interval;
+        }
+        if let Err(e) = terminal.draw(|frame| dashboard.draw(frame)) {
+            break Err(e);
+        }
+        let timeout = if dashboard.paused {
+            interval
+        } else {
+            next.saturating_duration_since(Instant::now())
+        };
+        match event::poll(timeout).and_then(|ready| ready.then(event::read).transpose()) {
+            Ok(Some(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                if dashboard.key(key.code) {
+                    break Ok(());
+                }
+            }
+            Ok(_) => {}
+            Err(e) => break Err(e),
+        }
+    };
+    ratatui::restore();
+    Ok(result?)
+}
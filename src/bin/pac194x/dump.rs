@@ -0,0 +1,173 @@
+//! Dumping and comparing the register map
+
+use crate::{Failure, Sensor};
+use serde_json::{Map, Value, json};
+use std::{fs, path::Path};
+
+/// A register (or channel register) of the map
+struct Register {
+    name: &'static str,
+    addr: u8,
+    size: usize,
+    /// Measurement results, which change on every REFRESH and aren't compared by `diff`
+    result: bool,
+}
+
+const fn reg(name: &'static str, addr: u8, size: usize) -> Register {
+    Register {
+        name,
+        addr,
+        size,
+        result: false,
+    }
+}
+
+const fn result(name: &'static str, addr: u8, size: usize) -> Register {
+    Register {
+        name,
+        addr,
+        size,
+        result: true,
+    }
+}
+
+/// Every readable register except ALERT_STATUS, which is cleared by reading it
+const REGISTERS: &[Register] = &[
+    reg("CTRL", 0x01, 2),
+    result("ACC_COUNT", 0x02, 4),
+    result("VACC1", 0x03, 7),
+    result("VACC2", 0x04, 7),
+    result("VACC3", 0x05, 7),
+    result("VACC4", 0x06, 7),
+    result("VBUS1", 0x07, 2),
+    result("VBUS2", 0x08, 2),
+    result("VBUS3", 0x09, 2),
+    result("VBUS4", 0x0A, 2),
+    result("VSENSE1", 0x0B, 2),
+    result("VSENSE2", 0x0C, 2),
+    result("VSENSE3", 0x0D, 2),
+    result("VSENSE4", 0x0E, 2),
+    result("VBUS1_AVG", 0x0F, 2),
+    result("VBUS2_AVG", 0x10, 2),
+    result("VBUS3_AVG", 0x11, 2),
+    result("VBUS4_AVG", 0x12, 2),
+    result("VSENSE1_AVG", 0x13, 2),
+    result("VSENSE2_AVG", 0x14, 2),
+    result("VSENSE3_AVG", 0x15, 2),
+    result("VSENSE4_AVG", 0x16, 2),
+    result("VPOWER1", 0x17, 4),
+    result("VPOWER2", 0x18, 4),
+    result("VPOWER3", 0x19, 4),
+    result("VPOWER4", 0x1A, 4),
+    reg("SMBUS_SETTINGS", 0x1C, 1),
+    reg("NEG_PWR_FSR", 0x1D, 2),
+    reg("SLOW", 0x20, 1),
+    reg("CTRL_ACT", 0x21, 2),
+    reg("NEG_PWR_FSR_ACT", 0x22, 2),
+    reg("CTRL_LAT", 0x23, 2),
+    reg("NEG_PWR_FSR_LAT", 0x24, 2),
+    reg("ACCUM_CONFIG", 0x25, 1),
+    reg("SLOW_ALERT1", 0x27, 3),
+    reg("GPIO_ALERT2", 0x28, 3),
+    reg("ACC_FULLNESS_LIMITS", 0x29, 2),
+    reg("OC_LIMIT1", 0x30, 2),
+    reg("OC_LIMIT2", 0x31, 2),
+    reg("OC_LIMIT3", 0x32, 2),
+    reg("OC_LIMIT4", 0x33, 2),
+    reg("UC_LIMIT1", 0x34, 2),
+    reg("UC_LIMIT2", 0x35, 2),
+    reg("UC_LIMIT3", 0x36, 2),
+    reg("UC_LIMIT4", 0x37, 2),
+    reg("OP_LIMIT1", 0x38, 3),
+    reg("OP_LIMIT2", 0x39, 3),
+    reg("OP_LIMIT3", 0x3A, 3),
+    reg("OP_LIMIT4", 0x3B, 3),
+    reg("OV_LIMIT1", 0x3C, 2),
+    reg("OV_LIMIT2", 0x3D, 2),
+    reg("OV_LIMIT3", 0x3E, 2),
+    reg("OV_LIMIT4", 0x3F, 2),
+    reg("UV_LIMIT1", 0x40, 2),
+    reg("UV_LIMIT2", 0x41, 2),
+    reg("UV_LIMIT3", 0x42, 2),
+    reg("UV_LIMIT4", 0x43, 2),
+    reg("OC_LIMIT_NSAMPLES", 0x44, 1),
+    reg("UC_LIMIT_NSAMPLES", 0x45, 1),
+    reg("OP_LIMIT_NSAMPLES", 0x46, 1),
+    reg("OV_LIMIT_NSAMPLES", 0x47, 1),
+    reg("UV_LIMIT_NSAMPLES", 0x48, 1),
+    reg("ALERT_ENABLE", 0x49, 3),
+    reg("ACCUM_CONFIG_ACT", 0x4A, 1),
+    reg("ACCUM_CONFIG_LAT", 0x4B, 1),
+    reg("PRODUCT_ID", 0xFD, 1),
+    reg("MANUFACTURER_ID", 0xFE, 1),
+    reg("REVISION_ID", 0xFF, 1),
+];
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02X}")).collect()
+}
+
+/// Reads every register as a hex string
+fn read_all(sensor: &mut Sensor) -> Result<Vec<String>, Failure> {
+    let mut buf = [0u8; 7];
+    REGISTERS
+        .iter()
+        .map(|r| {
+            sensor.read_register(r.addr, &mut buf[..r.size])?;
+            Ok(hex(&buf[..r.size]))
+        })
+        .collect()
+}
+
+pub fn dump(sensor: &mut Sensor, json: bool) -> Result<(), Failure> {
+    let values = read_all(sensor)?;
+    if json {
+        let registers: Map<String, Value> = REGISTERS
+            .iter()
+            .zip(values)
+            .map(|(r, v)| (r.name.to_string(), v.into()))
+            .collect();
+        let dump = json!({
+            "address": format!("{:#04x}", sensor.address()),
+            "registers": registers,
+        });
+        println!("{}", serde_json::to_string_pretty(&dump)?);
+    } else {
+        for (r, v) in REGISTERS.iter().zip(values) {
+            println!("{:#04x} {:<20} {v}", r.addr, r.name);
+        }
+    }
+    Ok(())
+}
+
+pub fn diff(sensor: &mut Sensor, file: &Path, json: bool) -> Result<(), Failure> {
+    let saved: Value = serde_json::from_str(&fs::read_to_string(file)?)?;
+    let saved = saved["registers"]
+        .as_object()
+        .ok_or_else(|| Failure(format!("{}: not a register dump", file.display())))?;
+    let mut changes = Vec::new();
+    for (r, current) in REGISTERS.iter().zip(read_all(sensor)?) {
+        let Some(before) = saved.get(r.name).and_then(Value::as_str) else {
+            continue;
+        };
+        if !r.result && !before.eq_ignore_ascii_case(&current) {
+            changes.push((r, before.to_string(), current));
+        }
+    }
+    if json {
+        let changes: Vec<Value> = changes
+            .iter()
+            .map(
+                |(r, before, after)| json!({"register": r.name, "saved": before, "current": after}),
+            )
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&changes)?);
+    } else if changes.is_empty() {
+        println!("No differences");
+    } else {
+        for (r, before, after) in changes {
+            println!("{:#04x} {:<20} {before} -> {after}", r.addr, r.name);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+//! Continuous logging of the rails to CSV or JSON Lines
+
+use crate::{Cli, Failure, micro, open};
+use embedded_hal_bus::i2c::RefCellDevice;
+use pac194x::config_file::ConfigFile;
+use pac194x::logger::{Format, Logger, Rotation};
+use pac194x::rail::{BoardBuf, Rail};
+use pac194x::{AddrSelect, PAC194X};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{thread, time::Duration};
+
+/// How to log, from the command line
+pub struct Options<'a> {
+    pub output: &'a Path,
+    pub config: Option<&'a PathBuf>,
+    pub shunts: &'a [(u8, f64)],
+    pub interval: f64,
+    pub format: Option<Format>,
+    pub rotate_size: Option<u64>,
+    pub rotate_secs: Option<u64>,
+}
+
+/// The rails of the configuration file, or the channels with a `--shunt` of the device at `--address`
+fn board(cli: &Cli, options: &Options) -> Result<BoardBuf, Failure> {
+    if let Some(config) = options.config {
+        return Ok(ConfigFile::load(config)?.board);
+    }
+    let addr_sel = AddrSelect::from_address(cli.address).expect("checked by parse_address");
+    let mut board = BoardBuf::new();
+    for (n, shunt) in options.shunts {
+        board.push(Rail::new(
+            format!("CH{n}"),
+            addr_sel,
+            *n,
+            micro(*shunt) as u32,
+        ));
+    }
+    board
+        .as_board()
+        .check()
+        .map_err(|e| Failure(format!("invalid shunts: {e:?}")))?;
+    Ok(board)
+}
+
+pub fn log(cli: &Cli, options: &Options) -> Result<(), Failure> {
+    let board = board(cli, options)?;
+    if board.rails.is_empty() {
+        return Err(Failure("nothing to log: give --config or --shunt".into()));
+    }
+    let format = options
+        .format
+        .or_else(|| Format::from_path(options.output))
+        .ok_or_else(|| Failure("use a .csv or .jsonl output file or give --format".into()))?;
+    let rotation = match (options.rotate_size, options.rotate_secs) {
+        (Some(size), _) => Rotation::Size(size),
+        (None, Some(secs)) => Rotation::Age(Duration::from_secs(secs)),
+        (None, None) => Rotation::Never,
+    };
+    let interval = Duration::try_from_secs_f64(options.interval)
+        .ok()
+        .filter(|i| !i.is_zero())
+        .ok_or_else(|| Failure("--interval must be a positive number of seconds".into()))?;
+
+    let mut addr_sels: Vec<AddrSelect> = board.rails.iter().map(|r| r.addr_sel).collect();
+    addr_sels.sort_by_key(|a| *a as u8);
+    addr_sels.dedup();
+    let i2c = RefCell::new(open(&cli.bus)?);
+    let mut sensors = addr_sels
+        .into_iter()
+        .map(|addr_sel| PAC194X::new(RefCellDevice::new(&i2c), addr_sel))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let handler = stop.clone();
+    ctrlc::set_handler(move || handler.store(true, Ordering::Relaxed))
+        .map_err(|e| Failure(e.to_string()))?;
+
+    let mut logger = Logger::create(options.output, format, rotation, board)?;
+    eprintln!(
+        "Logging to {}, press Ctrl-C to stop",
+        logger.path().display()
+    );
+    // REFRESH_V keeps the accumulators running, which the logger integrates the energy from
+    logger.run(interval, &stop, || {
+        for sensor in &mut sensors {
+            sensor.refresh_v()?;
+        }
+        thread::sleep(Duration::from_millis(1));
+        sensors.iter_mut().map(|s| s.read_measurements()).collect()
+    })?;
+    eprintln!("Stopped, last file {}", logger.path().display());
+    Ok(())
+}
@@ -0,0 +1,778 @@
+//! Command line tool for PAC194X/PAC195X power monitors on Linux i2c-dev
+
+#[cfg(feature = "tui")]
+mod dashboard;
+mod dump;
+mod log;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use linux_embedded_hal::I2cdev;
+use pac194x::config_file::{ConfigFile, LoadError};
+use pac194x::logger::{Format, LogError};
+use pac194x::regs::{LimitAlert, SampleCount, SampleMode, VBusFSR, VSenseFSR};
+use pac194x::scan::{self, Responder};
+use pac194x::{AddrSelect, Error, PAC194X, convert};
+use serde_json::{Value, json};
+use std::{fmt::Debug, io, path::PathBuf, process::ExitCode, thread, time::Duration};
+
+type Sensor = PAC194X<I2cdev>;
+
+/// Inspect and configure PAC194X/PAC195X power monitors
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    /// I2C bus device
+    #[arg(short, long, default_value = "/dev/i2c-1", global = true)]
+    bus: String,
+    /// Device address: the ADDRSEL resistor value (GND, 499, ..., VDD) or the 7-bit address (0x10 to 0x1F)
+    #[arg(short, long, default_value = "GND", value_parser = parse_address, global = true)]
+    address: u8,
+    /// Print JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List the devices on the bus
+    Scan,
+    /// Print the part, revision and capabilities of the device
+    Identify,
+    /// Read the measurements of every channel
+    Read {
+        /// Sense resistor value of a channel in ohms, e.g. 1=0.01
+        #[arg(long = "shunt", value_parser = parse_channel_value::<f64>)]
+        shunts: Vec<(u8, f64)>,
+        /// Take the rail names and sense resistor values from a configuration file
+        #[arg(long)]
+        config: Option<PathBuf>,
+        /// Read the results of the last refresh instead of sending REFRESH_V first
+        #[arg(long)]
+        no_refresh: bool,
+    },
+    /// Print every register except ALERT_STATUS
+    Dump,
+    /// Compare the configuration registers to a file written by `dump --json`
+    Diff { file: PathBuf },
+    /// Change the configuration and activate it with a REFRESH, which also resets the accumulators
+    Set {
+        #[arg(long, value_enum)]
+        sample_mode: Option<Mode>,
+        /// VBUS range of a channel, e.g. 1=bipolar
+        #[arg(long, value_parser = parse_channel_value::<Fsr>)]
+        vbus_fsr: Vec<(u8, Fsr)>,
+        /// VSENSE range of a channel, e.g. 2=bipolar-half
+        #[arg(long, value_parser = parse_channel_value::<Fsr>)]
+        vsense_fsr: Vec<(u8, Fsr)>,
+        /// Enable a channel
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=4))]
+        enable: Vec<u8>,
+        /// Disable a channel
+        #[arg(long, value_parser = clap::value_parser!(u8).range(1..=4))]
+        disable: Vec<u8>,
+    },
+    /// Set and enable the ALERT limits of a channel, then REFRESH to activate them
+    Limits {
+        /// Channel, 1 to 4
+        #[arg(short, long, value_parser = clap::value_parser!(u8).range(1..=4))]
+        channel: u8,
+        /// Sense resistor value in ohms, needed for current and power limits
+        #[arg(long)]
+        shunt: Option<f64>,
+        /// Overvoltage limit in volts
+        #[arg(long, allow_negative_numbers = true)]
+        ov: Option<f64>,
+        /// Undervoltage limit in volts
+        #[arg(long, allow_negative_numbers = true)]
+        uv: Option<f64>,
+        /// Overcurrent limit in amps
+        #[arg(long, allow_negative_numbers = true)]
+        oc: Option<f64>,
+        /// Undercurrent limit in amps
+        #[arg(long, allow_negative_numbers = true)]
+        uc: Option<f64>,
+        /// Overpower limit in watts
+        #[arg(long, allow_negative_numbers = true)]
+        op: Option<f64>,
+        /// Consecutive samples to trigger the ALERTs: 1, 4, 8 or 16
+        #[arg(long)]
+        samples: Option<u8>,
+        /// Disable the ALERTs of the channel that aren't given
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Apply a configuration file to every device it describes
+    Apply { file: PathBuf },
+    /// Log the rails to CSV or JSON Lines until Ctrl-C
+    Log {
+        /// Output file, .csv or .jsonl
+        output: PathBuf,
+        /// Log the rails of a configuration file instead of the channels given with --shunt
+        #[arg(long)]
+        config: Option<PathBuf>,
+        /// Sense resistor value of a channel to log in ohms, e.g. 1=0.01
+        #[arg(long = "shunt", value_parser = parse_channel_value::<f64>)]
+        shunts: Vec<(u8, f64)>,
+        /// Seconds between samples
+        #[arg(long, default_value = "1")]
+        interval: f64,
+        /// Output format, instead of guessing it from the file extension
+        #[arg(long, value_enum)]
+        format: Option<LogFormat>,
+        /// Start a new file once it holds this many bytes
+        #[arg(long, conflicts_with = "rotate_secs")]
+        rotate_size: Option<u64>,
+        /// Start a new file after this many seconds
+        #[arg(long)]
+        rotate_secs: Option<u64>,
+    },
+    /// Show every channel of every device on the bus in a live dashboard
+    #[cfg(feature = "tui")]
+    Dashboard {
+        /// Take the rail names and sense resistor values from a configuration file
+        #[arg(long)]
+        config: Option<PathBuf>,
+        /// Sense resistor value in ohms of the channels without a rail in the configuration file
+        #[arg(long)]
+        shunt: Option<f64>,
+        /// Seconds between samples
+        #[arg(long, default_value = "0.5")]
+        interval: f64,
+    },
+    /// Send a refresh command
+    Refresh {
+        #[arg(long, value_enum, default_value = "full")]
+        kind: RefreshKind,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Mode {
+    #[value(name = "1024-adaptive")]
+    _1024Adaptive,
+    #[value(name = "256-adaptive")]
+    _256Adaptive,
+    #[value(name = "64-adaptive")]
+    _64Adaptive,
+    #[value(name = "8-adaptive")]
+    _8Adaptive,
+    #[value(name = "1024")]
+    _1024,
+    #[value(name = "256")]
+    _256,
+    #[value(name = "64")]
+    _64,
+    #[value(name = "8")]
+    _8,
+    SingleShot,
+    SingleShot8x,
+    Fast,
+    Burst,
+    Sleep,
+}
+
+impl From<Mode> for SampleMode {
+    fn from(mode: Mode) -> Self {
+        match mode {
+            Mode::_1024Adaptive => Self::_1024Adaptive,
+            Mode::_256Adaptive => Self::_256Adaptive,
+            Mode::_64Adaptive => Self::_64Adaptive,
+            Mode::_8Adaptive => Self::_8Adaptive,
+            Mode::_1024 => Self::_1024,
+            Mode::_256 => Self::_256,
+            Mode::_64 => Self::_64,
+            Mode::_8 => Self::_8,
+            Mode::SingleShot => Self::SingleShot,
+            Mode::SingleShot8x => Self::SingleShot8X,
+            Mode::Fast => Self::Fast,
+            Mode::Burst => Self::Burst,
+            Mode::Sleep => Self::Sleep,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Fsr {
+    Unipolar,
+    Bipolar,
+    BipolarHalf,
+}
+
+impl std::str::FromStr for Fsr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        <Self as ValueEnum>::from_str(s, true)
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LogFormat {
+    Csv,
+    Jsonl,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum RefreshKind {
+    /// REFRESH, which resets the accumulators
+    Full,
+    /// REFRESH_V, which keeps the accumulators
+    V,
+    /// REFRESH_G to every device on the bus
+    G,
+}
+
+/// A failed command, with the message to print
+#[derive(Debug)]
+struct Failure(String);
+
+impl<E: Debug> From<Error<E>> for Failure {
+    fn from(e: Error<E>) -> Self {
+        Self(match e {
+            Error::I2c(e) => format!("I2C error: {e:?}"),
+            e => format!("{e:?}"),
+        })
+    }
+}
+
+impl<E: Debug> From<LogError<Error<E>>> for Failure {
+    fn from(e: LogError<Error<E>>) -> Self {
+        match e {
+            LogError::Device(e) => e.into(),
+            LogError::Io(e) => e.into(),
+        }
+    }
+}
+
+impl From<LoadError> for Failure {
+    fn from(e: LoadError) -> Self {
+        Self(e.to_string())
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(e: io::Error) -> Self {
+        Self(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Failure {
+    fn from(e: serde_json::Error) -> Self {
+        Self(e.to_string())
+    }
+}
+
+fn parse_address(s: &str) -> Result<u8, String> {
+    if let Some(addr_sel) = AddrSelect::from_name(s) {
+        return Ok(addr_sel as u8);
+    }
+    s.strip_prefix("0x")
+        .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        .filter(|a| AddrSelect::from_address(*a).is_some())
+        .ok_or_else(|| format!("{s} is neither an ADDRSEL value nor an address from 0x10 to 0x1F"))
+}
+
+fn parse_channel_value<T: std::str::FromStr>(s: &str) -> Result<(u8, T), String> {
+    let (n, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("{s} isn't CHANNEL=VALUE"))?;
+    let n = n
+        .parse()
+        .ok()
+        .filter(|n| (1..=4).contains(n))
+        .ok_or_else(|| format!("channel {n} isn't between 1 and 4"))?;
+    let value = value
+        .parse()
+        .map_err(|_| format!("invalid value {value}"))?;
+    Ok((n, value))
+}
+
+fn open(bus: &str) -> Result<I2cdev, Failure> {
+    I2cdev::new(bus).map_err(|e| Failure(format!("{bus}: {e}")))
+}
+
+fn connect(cli: &Cli) -> Result<Sensor, Failure> {
+    Ok(PAC194X::new_with_address(open(&cli.bus)?, cli.address)?)
+}
+
+fn print_json(value: &Value) -> Result<(), Failure> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+fn micro(value: f64) -> i64 {
+    (value * 1e6).round() as i64
+}
+
+fn unit(micro: i64) -> f64 {
+    micro as f64 / 1e6
+}
+
+fn scan(cli: &Cli) -> Result<(), Failure> {
+    let mut i2c = open(&cli.bus)?;
+    let scan = scan::scan(&mut i2c)?;
+    let entries: Vec<Value> = scan
+        .iter()
+        .map(|(addr_sel, responder)| {
+            let mut entry = json!({
+                "address": format!("{:#04x}", addr_sel as u8),
+                "addr_sel": addr_sel.name(),
+            });
+            entry["device"] = match responder {
+                Responder::Device(d) => json!({
+                    "product": d.product_id.name(),
+                    "revision": d.revision_id,
+                    "channels": d.channels(),
+                    "max_voltage": unit(d.max_voltage_uv() as i64),
+                }),
+                Responder::Conflict {
+                    product_id,
+                    manufacturer_id,
+                    revision_id,
+                } => json!({
+                    "conflict": true,
+                    "product_id": product_id,
+                    "manufacturer_id": manufacturer_id,
+                    "revision_id": revision_id,
+                }),
+                Responder::Foreign { manufacturer_id } => json!({
+                    "foreign": true,
+                    "manufacturer_id": manufacturer_id,
+                }),
+            };
+            entry
+        })
+        .collect();
+    if cli.json {
+        return print_json(&entries.into());
+    }
+    if entries.is_empty() {
+        println!("No devices found on {}", cli.bus);
+    }
+    for (addr_sel, responder) in scan.iter() {
+        let address = addr_sel as u8;
+        let addr_sel = addr_sel.name();
+        match responder {
+            Responder::Device(d) => println!(
+                "{address:#04x} {addr_sel:<7} {} rev {:#04x}, {} channels, {} V",
+                d.product_id.name(),
+                d.revision_id,
+                d.channels(),
+                unit(d.max_voltage_uv() as i64)
+            ),
+            Responder::Conflict {
+                product_id,
+                manufacturer_id,
+                revision_id,
+            } => println!(
+                "{address:#04x} {addr_sel:<7} address conflict? product {product_id:#04x}, manufacturer {manufacturer_id:#04x}, revision {revision_id:#04x}"
+            ),
+            Responder::Foreign { manufacturer_id } => println!(
+                "{address:#04x} {addr_sel:<7} not a Microchip device, manufacturer {manufacturer_id:#04x}"
+            ),
+        }
+    }
+    Ok(())
+}
+
+fn identify(cli: &Cli) -> Result<(), Failure> {
+    let mut sensor = connect(cli)?;
+    let product = sensor.product_id()?;
+    let manufacturer = sensor.manufacturer_id()?;
+    let revision = sensor.revision_id()?;
+    if cli.json {
+        return print_json(&json!({
+            "address": format!("{:#04x}", cli.address),
+            "product": product.name(),
+            "manufacturer_id": manufacturer,
+            "revision_id": revision,
+            "channels": product.channels(),
+            "max_voltage": unit(product.max_voltage_uv() as i64),
+        }));
+    }
+    println!("Product:      {}", product.name());
+    println!("Manufacturer: {manufacturer:#04x}");
+    println!("Revision:     {revision:#04x}");
+    println!("Channels:     {}", product.channels());
+    println!("Full scale:   {} V", unit(product.max_voltage_uv() as i64));
+    Ok(())
+}
+
+fn read(
+    cli: &Cli,
+    shunts: &[(u8, f64)],
+    config: Option<&PathBuf>,
+    no_refresh: bool,
+) -> Result<(), Failure> {
+    let mut sensor = connect(cli)?;
+    let product = sensor.product_id()?;
+    let config = config.map(ConfigFile::load).transpose()?;
+    let rail = |n: u8| {
+        let board = config.as_ref()?.board.as_board();
+        board
+            .rails()
+            .iter()
+            .find(|r| r.addr_sel as u8 == cli.address && r.channel == n)
+    };
+    if !no_refresh {
+        sensor.refresh_v()?;
+        thread::sleep(Duration::from_millis(1));
+    }
+    let m = sensor.read_measurements()?;
+
+    let mut channels = Vec::new();
+    for n in 1..=product.channels() {
+        let rail = rail(n);
+        let shunt_uohm = shunts
+            .iter()
+            .rev()
+            .find(|(c, _)| *c == n)
+            .map(|(_, r)| micro(*r) as u32)
+            .or(rail.map(|r| r.shunt_uohm));
+        let mut channel = json!({
+            "channel": n,
+            "rail": rail.map(|r| r.name.as_str()),
+            "enabled": m.channel(n).is_some(),
+        });
+        if m.channel(n).is_some() {
+            channel["bus_voltage"] = unit(m.bus_voltage_uv(n).unwrap_or_default() as i64).into();
+            channel["sense_voltage"] =
+                unit(m.sense_voltage_uv(n).unwrap_or_default() as i64).into();
+            if let Some(shunt_uohm) = shunt_uohm.filter(|s| *s > 0) {
+                channel["current"] = m.current_ua(n, shunt_uohm).map(unit).into();
+                channel["power"] = m.power_uw(n, shunt_uohm).map(unit).into();
+            }
+        }
+        channels.push(channel);
+    }
+
+    if cli.json {
+        return print_json(&json!({
+            "address": format!("{:#04x}", cli.address),
+            "product": product.name(),
+            "acc_count": m.acc_count.count,
+            "channels": channels,
+        }));
+    }
+    println!("{} at {:#04x}", product.name(), cli.address);
+    for channel in channels {
+        let label = match channel["rail"].as_str() {
+            Some(rail) => format!("CH{} {rail}", channel["channel"]),
+            None => format!("CH{}", channel["channel"]),
+        };
+        if channel["enabled"] == false {
+            println!("{label:<16} off");
+            continue;
+        }
+        print!(
+            "{label:<16} {:>9.4} V {:>9.4} mV",
+            channel["bus_voltage"].as_f64().unwrap_or_default(),
+            channel["sense_voltage"].as_f64().unwrap_or_default() * 1e3
+        );
+        if let (Some(current), Some(power)) =
+            (channel["current"].as_f64(), channel["power"].as_f64())
+        {
+            print!(" {current:>9.4} A {power:>9.4} W");
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn set(
+    cli: &Cli,
+    sample_mode: Option<Mode>,
+    vbus_fsr: &[(u8, Fsr)],
+    vsense_fsr: &[(u8, Fsr)],
+    enable: &[u8],
+    disable: &[u8],
+) -> Result<(), Failure> {
+    let mut sensor = connect(cli)?;
+    sensor.batch(|s| {
+        if sample_mode.is_some() || !enable.is_empty() || !disable.is_empty() {
+            s.modify_ctrl(|ctrl| {
+                if let Some(mode) = sample_mode {
+                    ctrl.sample_mode = mode.into();
+                }
+                for n in enable {
+                    ctrl.channel_n_off.set(*n, false);
+                }
+                for n in disable {
+                    ctrl.channel_n_off.set(*n, true);
+                }
+            })?;
+        }
+        if !vbus_fsr.is_empty() || !vsense_fsr.is_empty() {
+            s.modify_neg_pwr_fsr(|fsr| {
+                for (n, range) in vbus_fsr {
+                    fsr.set_vbus_fsr(
+                        *n,
+                        match range {
+                            Fsr::Unipolar => VBusFSR::Unipolar,
+                            Fsr::Bipolar => VBusFSR::BipolarHV,
+                            Fsr::BipolarHalf => VBusFSR::BipolarLV,
+                        },
+                    );
+                }
+                for (n, range) in vsense_fsr {
+                    fsr.set_vsense_fsr(
+                        *n,
+                        match range {
+                            Fsr::Unipolar => VSenseFSR::Unipolar,
+                            Fsr::Bipolar => VSenseFSR::BipolarHV,
+                            Fsr::BipolarHalf => VSenseFSR::BipolarLV,
+                        },
+                    );
+                }
+            })?;
+        }
+        Ok(())
+    })?;
+    if !cli.json {
+        println!("Configuration updated");
+    }
+    Ok(())
+}
+
+/// The requested limits of a channel, in volts, amps and watts
+struct Limits {
+    shunt: Option<f64>,
+    ov: Option<f64>,
+    uv: Option<f64>,
+    oc: Option<f64>,
+    uc: Option<f64>,
+    op: Option<f64>,
+    samples: Option<u8>,
+    clear: bool,
+}
+
+fn limits(cli: &Cli, n: u8, limits: &Limits) -> Result<(), Failure> {
+    let mut sensor = connect(cli)?;
+    let max_uv = sensor.product_id()?.max_voltage_uv();
+    let fsr = sensor.read_neg_pwr_fsr()?;
+    let (vbus_fsr, vsense_fsr) = (
+        [fsr.cfg_vb1, fsr.cfg_vb2, fsr.cfg_vb3, fsr.cfg_vb4][n as usize - 1],
+        [fsr.cfg_vs1, fsr.cfg_vs2, fsr.cfg_vs3, fsr.cfg_vs4][n as usize - 1],
+    );
+    let shunt_uohm = || {
+        limits
+            .shunt
+            .map(|s| micro(s) as u32)
+            .filter(|s| *s > 0)
+            .ok_or_else(|| Failure("current and power limits need a positive --shunt".into()))
+    };
+    let out_of_range = |name| {
+        Failure(format!(
+            "the {name} limit is outside of the full scale range"
+        ))
+    };
+    let count = limits
+        .samples
+        .map(|s| {
+            SampleCount::from_samples(s).ok_or(Failure("--samples must be 1, 4, 8 or 16".into()))
+        })
+        .transpose()?;
+
+    // Convert everything before touching the device
+    let ov = limits
+        .ov
+        .map(|v| convert::uv_to_vbus_limit(micro(v), max_uv, vbus_fsr).ok_or(out_of_range("OV")))
+        .transpose()?;
+    let uv = limits
+        .uv
+        .map(|v| convert::uv_to_vbus_limit(micro(v), max_uv, vbus_fsr).ok_or(out_of_range("UV")))
+        .transpose()?;
+    let oc = limits
+        .oc
+        .map(|a| {
+            convert::ua_to_vsense_limit(micro(a), vsense_fsr, shunt_uohm()?)
+                .ok_or(out_of_range("OC"))
+        })
+        .transpose()?;
+    let uc = limits
+        .uc
+        .map(|a| {
+            convert::ua_to_vsense_limit(micro(a), vsense_fsr, shunt_uohm()?)
+                .ok_or(out_of_range("UC"))
+        })
+        .transpose()?;
+    let op = limits
+        .op
+        .map(|w| {
+            convert::uw_to_power_limit(micro(w), max_uv, vbus_fsr, vsense_fsr, shunt_uohm()?)
+                .ok_or(out_of_range("OP"))
+        })
+        .transpose()?;
+
+    // Disable the ALERTs of the channel while its limits change to avoid false triggers, ALERT_ENABLE
+    // only takes effect on a REFRESH
+    let mut enable = sensor.read_alert_enable()?;
+    let before = enable;
+    for alert in LimitAlert::ALL {
+        enable.set_limit(alert, n, false);
+    }
+    sensor.write_alert_enable(enable)?;
+    sensor.refresh_v()?;
+    sensor.batch(|s| {
+        if let Some(limit) = ov {
+            s.modify_ov_limitn(n, |l| l.limit = limit)?;
+        }
+        if let Some(limit) = uv {
+            s.modify_uv_limitn(n, |l| l.limit = limit)?;
+        }
+        if let Some(limit) = oc {
+            s.modify_oc_limitn(n, |l| l.limit = limit)?;
+        }
+        if let Some(limit) = uc {
+            s.modify_uc_limitn(n, |l| l.limit = limit)?;
+        }
+        if let Some(limit) = op {
+            s.modify_op_limitn(n, |l| l.limit = limit)?;
+        }
+        if let Some(count) = count {
+            s.modify_oc_limit_n_samples(|r| r.set_n_samples(n, count))?;
+            s.modify_uc_limit_n_samples(|r| r.set_n_samples(n, count))?;
+            s.modify_op_limit_n_samples(|r| r.set_n_samples(n, count))?;
+            s.modify_ov_limit_n_samples(|r| r.set_n_samples(n, count))?;
+            s.modify_uv_limit_n_samples(|r| r.set_n_samples(n, count))?;
+        }
+        for (alert, given) in [
+            (LimitAlert::Ov, ov.is_some()),
+            (LimitAlert::Uv, uv.is_some()),
+            (LimitAlert::Oc, oc.is_some()),
+            (LimitAlert::Uc, uc.is_some()),
+            (LimitAlert::Op, op.is_some()),
+        ] {
+            let keep = !limits.clear && before.limit(alert, n);
+            enable.set_limit(alert, n, given || keep);
+        }
+        s.write_alert_enable(enable)
+    })?;
+    if !cli.json {
+        println!("Limits of channel {n} updated");
+    }
+    Ok(())
+}
+
+fn apply(cli: &Cli, file: &PathBuf) -> Result<(), Failure> {
+    let config = ConfigFile::load(file)?;
+    let mut i2c = open(&cli.bus)?;
+    for device in &config.devices {
+        let mut sensor = PAC194X::new(&mut i2c, device.addr_sel)?;
+        device.apply(&mut sensor)?;
+        if !cli.json {
+            println!(
+                "Configured {} at {:#04x}",
+                device.product_id.name(),
+                device.addr_sel as u8
+            );
+        }
+    }
+    Ok(())
+}
+
+fn refresh(cli: &Cli, kind: RefreshKind) -> Result<(), Failure> {
+    let mut sensor = connect(cli)?;
+    match kind {
+        RefreshKind::Full => sensor.refresh()?,
+        RefreshKind::V => sensor.refresh_v()?,
+        RefreshKind::G => sensor.refresh_g()?,
+    }
+    Ok(())
+}
+
+fn run(cli: &Cli) -> Result<(), Failure> {
+    match &cli.command {
+        Command::Scan => scan(cli),
+        Command::Identify => identify(cli),
+        Command::Read {
+            shunts,
+            config,
+            no_refresh,
+        } => read(cli, shunts, config.as_ref(), *no_refresh),
+        Command::Dump => dump::dump(&mut connect(cli)?, cli.json),
+        Command::Diff { file } => dump::diff(&mut connect(cli)?, file, cli.json),
+        Command::Set {
+            sample_mode,
+            vbus_fsr,
+            vsense_fsr,
+            enable,
+            disable,
+        } => set(cli, *sample_mode, vbus_fsr, vsense_fsr, enable, disable),
+        Command::Limits {
+            channel,
+            shunt,
+            ov,
+            uv,
+            oc,
+            uc,
+            op,
+            samples,
+            clear,
+        } => limits(
+            cli,
+            *channel,
+            &Limits {
+                shunt: *shunt,
+                ov: *ov,
+                uv: *uv,
+                oc: *oc,
+                uc: *uc,
+                op: *op,
+                samples: *samples,
+                clear: *clear,
+            },
+        ),
+        Command::Apply { file } => apply(cli, file),
+        Command::Log {
+            output,
+            config,
+            shunts,
+            interval,
+            format,
+            rotate_size,
+            rotate_secs,
+        } => log::log(
+            cli,
+            &log::Options {
+                output,
+                config: config.as_ref(),
+                shunts,
+                interval: *interval,
+                format: format.map(|f| match f {
+                    LogFormat::Csv => Format::Csv,
+                    LogFormat::Jsonl => Format::JsonLines,
+                }),
+                rotate_size: *rotate_size,
+                rotate_secs: *rotate_secs,
+            },
+        ),
+        #[cfg(feature = "tui")]
+        Command::Dashboard {
+            config,
+            shunt,
+            interval,
+        } => dashboard::dashboard(
+            cli,
+            &dashboard::Options {
+                config: config.as_ref(),
+                shunt: *shunt,
+                interval: *interval,
+            },
+        ),
+        Command::Refresh { kind } => refresh(cli, *kind),
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure(message)) => {
+            eprintln!("error: {message}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,164 @@
+//! Enabling and disabling channels
+//!
+//! A channel is disabled by its bit in [`Ctrl::channel_n_off`], but the change only takes effect at the
+//! next REFRESH, and [`CtrlAct`] reports the channels that are actually active. [`PAC194X::enable_channel`],
+//! [`PAC194X::disable_channel`] and [`PAC194X::set_channels_off`] write [`Ctrl`], issue REFRESH_V, which
+//! leaves the accumulators running, and wait until [`CtrlAct`] shows the new channels.
+//!
+//! The result registers of a disabled channel keep whatever they last held, so the per-channel read APIs
+//! return [`Error::ChannelDisabled`] for it and [`PAC194X::read_measurements`] reports it as `None`. Both
+//! read [`CtrlAct`] each time, so they also follow a REFRESH the driver didn't issue, such as a REFRESH_G
+//! from another driver on the bus or a refresh by the SLOW pin.
+
+use crate::regs::*;
+use crate::{Error, PAC194X};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+
+/// How long to wait in milliseconds for [`CtrlAct`] to follow [`Ctrl`], one conversion cycle at 8 SPS
+const CONFIRM_MS: u32 = 125;
+
+impl<E, I> PAC194X<I>
+where
+    I: I2c<Error = E>,
+{
+    /// Enables channel `n` and waits until it is active
+    ///
+    /// Returns the channels that are off afterwards. See [`set_channels_off`](Self::set_channels_off).
+    pub fn enable_channel<D: DelayNs>(
+        &mut self,
+        n: u8,
+        delay: &mut D,
+    ) -> Result<Channels, Error<E>> {
+        self.switch_channel(n, false, delay)
+    }
+
+    /// Disables channel `n` and waits until it is inactive
+    ///
+    /// Returns the channels that are off afterwards. See [`set_channels_off`](Self::set_channels_off).
+    pub fn disable_channel<D: DelayNs>(
+        &mut self,
+        n: u8,
+        delay: &mut D,
+    ) -> Result<Channels, Error<E>> {
+        self.switch_channel(n, true, delay)
+    }
+
+    fn switch_channel<D: DelayNs>(
+        &mut self,
+        n: u8,
+        off: bool,
+        delay: &mut D,
+    ) -> Result<Channels, Error<E>> {
+        if !(1..=self.product_id.channels()).contains(&n) {
+            return Err(Error::NoSuchChannel(n));
+        }
+        let mut channels = self.read_ctrl()?.channel_n_off;
+        channels.set(n, off);
+        self.set_channels_off(channels, delay)?;
+        Ok(channels)
+    }
+
+    /// Turns off the channels set in `off` and turns on the others
+    ///
+    /// Writes [`Ctrl`], issues REFRESH_V and polls [`CtrlAct`] every millisecond on `delay`. A change of
+    /// the sample mode still pending from an earlier write can hold [`CtrlAct`] back until the current
+    /// conversion cycle completes, so this waits up to 125 ms, a cycle at 8 SPS, before returning
+    /// [`Error::ChannelsNotApplied`] with the channels that are active.
+    pub fn set_channels_off<D: DelayNs>(
+        &mut self,
+        off: Channels,
+        delay: &mut D,
+    ) -> Result<(), Error<E>> {
+        self.modify_ctrl(|ctrl| ctrl.channel_n_off = off)?;
+        self.refresh_v()?;
+        let mut waited = 0;
+        loop {
+            delay.delay_ms(1);
+            let active = self.read_ctrl_act()?.channel_n_off;
+            if active == off {
+                return Ok(());
+            }
+            waited += 1;
+            if waited == CONFIRM_MS {
+                return Err(Error::ChannelsNotApplied(active));
+            }
+        }
+    }
+
+    /// Whether channel `n` (1 to 4) is active according to [`CtrlAct`], which is read every time
+    ///
+    /// Returns [`Error::NoSuchChannel`] for any other `n`.
+    pub fn is_channel_enabled(&mut self, n: u8) -> Result<bool, Error<E>> {
+        if !(1..=4).contains(&n) {
+            return Err(Error::NoSuchChannel(n));
+        }
+        Ok(!self.read_ctrl_act()?.channel_n_off.get(n))
+    }
+
+    /// Returns [`Error::ChannelDisabled`] unless channel `n` (1 to 4) is active
+    pub(crate) fn check_enabled(&mut self, n: u8) -> Result<(), Error<E>> {
+        if self.is_channel_enabled(n)? {
+            Ok(())
+        } else {
+            Err(Error::ChannelDisabled(n))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AddrSelect;
+    use crate::regs::Address;
+    use crate::sim::SimBus;
+    use embedded_hal_mock::eh1::delay::NoopDelay;
+
+    #[test]
+    fn enable_and_disable() {
+        let bus = SimBus::new(&[0x10]);
+        let mut sensor = PAC194X::new(bus, AddrSelect::GND).unwrap();
+        sensor
+            .i2c
+            .with(0x10, |dev| dev.set_n(Address::Vbusn, 2, &[0x80, 0x00]));
+        assert_eq!(sensor.read_bus_voltage_uv_n(2), Ok(4_500_000));
+
+        let off = sensor.disable_channel(2, &mut NoopDelay).unwrap();
+        assert_eq!(
+            off,
+            Channels {
+                _2: true,
+                ..Default::default()
+            }
+        );
+        assert_eq!(sensor.is_channel_enabled(2), Ok(false));
+        assert_eq!(
+            sensor.read_bus_voltage_uv_n(2),
+            Err(Error::ChannelDisabled(2))
+        );
+        assert_eq!(
+            sensor.read_current_ua_n(2, 10_000),
+            Err(Error::ChannelDisabled(2))
+        );
+        assert!(sensor.read_measurements().unwrap().channel(2).is_none());
+        assert_eq!(sensor.read_bus_voltage_uv_n(1), Ok(0));
+
+        sensor.enable_channel(2, &mut NoopDelay).unwrap();
+        assert_eq!(sensor.read_bus_voltage_uv_n(2), Ok(4_500_000));
+        assert!(sensor.read_measurements().unwrap().channel(2).is_some());
+        // Disabled by a REFRESH the driver didn't issue
+        sensor
+            .i2c
+            .with(0x10, |dev| dev.set(Address::CtrlAct, &[0, 0b0100_0000]));
+        assert_eq!(
+            sensor.read_bus_voltage_uv_n(2),
+            Err(Error::ChannelDisabled(2))
+        );
+        assert!(sensor.read_measurements().unwrap().channel(2).is_none());
+        assert_eq!(sensor.is_channel_enabled(0), Err(Error::NoSuchChannel(0)));
+        assert_eq!(
+            sensor.enable_channel(5, &mut NoopDelay),
+            Err(Error::NoSuchChannel(5))
+        );
+    }
+}
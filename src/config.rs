@@ -0,0 +1,256 @@
+//! Whole-device configuration and power-on reset (POR) monitoring
+
+use crate::regs::*;
+use crate::{Error, PAC194X};
+use embedded_hal::i2c::I2c;
+use packed_struct::prelude::*;
+
+/// A snapshot of every writable configuration register of the device.
+///
+/// Use [`PAC194X::read_config`] to capture the current programming of a device and
+/// [`PAC194X::write_config`] to apply it, for example after a reset.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Config {
+    pub ctrl: Ctrl,
+    pub neg_pwr_fsr: NegPwrFsr,
+    pub accum_config: AccumConfig,
+    pub slow: Slow,
+    /// The [`SmbusSettings::por`] bit is ignored and always cleared when written. The
+    /// [`SmbusSettings::byte_count`] bit is ignored and kept as the driver has it, see
+    /// [`PAC194X::enable_byte_count`].
+    pub smbus_settings: SmbusSettings,
+    pub slow_alert1: SlowAlert1,
+    pub gpio_alert2: GpioAlert2,
+    pub acc_fullness_limits: AccFullnessLimits,
+    /// Overcurrent limits for channels 1 through 4
+    pub oc_limits: [OcLimitn; 4],
+    /// Undercurrent limits for channels 1 through 4
+    pub uc_limits: [UcLimitn; 4],
+    /// Overpower limits for channels 1 through 4
+    pub op_limits: [OpLimitn; 4],
+    /// Overvoltage limits for channels 1 through 4
+    pub ov_limits: [OvLimitn; 4],
+    /// Undervoltage limits for channels 1 through 4
+    pub uv_limits: [UvLimitn; 4],
+    pub oc_limit_n_samples: OcLimitNSamples,
+    pub uc_limit_n_samples: UcLimitNSamples,
+    pub op_limit_n_samples: OpLimitNSamples,
+    pub ov_limit_n_samples: OvLimitNSamples,
+    pub uv_limit_n_samples: UvLimitNSamples,
+    pub alert_enable: AlertEnable,
+}
+
+impl Default for Config {
+    /// The power-on defaults of the device
+    fn default() -> Self {
+        Self {
+            ctrl: Ctrl::unpack(&[0x07, 0x00]).unwrap(),
+            neg_pwr_fsr: NegPwrFsr::unpack(&[0x00, 0x00]).unwrap(),
+            accum_config: AccumConfig::unpack(&[0x00]).unwrap(),
+            slow: Slow::unpack(&[0x15]).unwrap(),
+            smbus_settings: SmbusSettings::unpack(&[0x10]).unwrap(),
+            slow_alert1: SlowAlert1::unpack(&[0; 3]).unwrap(),
+            gpio_alert2: GpioAlert2::unpack(&[0; 3]).unwrap(),
+            acc_fullness_limits: AccFullnessLimits::unpack(&[0x55, 0x40]).unwrap(),
+            oc_limits: [OcLimitn { limit: i16::MAX }; 4],
+            uc_limits: [UcLimitn { limit: i16::MIN }; 4],
+            op_limits: [OpLimitn { limit: 0x7F_FFFF }; 4],
+            ov_limits: [OvLimitn { limit: i16::MAX }; 4],
+            uv_limits: [UvLimitn { limit: i16::MIN }; 4],
+            oc_limit_n_samples: OcLimitNSamples::unpack(&[0]).unwrap(),
+            uc_limit_n_samples: UcLimitNSamples::unpack(&[0]).unwrap(),
+            op_limit_n_samples: OpLimitNSamples::unpack(&[0]).unwrap(),
+            ov_limit_n_samples: OvLimitNSamples::unpack(&[0]).unwrap(),
+            uv_limit_n_samples: UvLimitNSamples::unpack(&[0]).unwrap(),
+            alert_enable: AlertEnable::unpack(&[0; 3]).unwrap(),
+        }
+    }
+}
+
+impl<E, I> PAC194X<I>
+where
+    I: I2c<Error = E>,
+{
+    /// Reads every writable configuration register into a [`Config`]
+    pub fn read_config(&mut self) -> Result<Config, Error<E>> {
+        let mut limit = |n| -> Result<_, Error<E>> {
+            Ok((
+                self.read_oc_limitn(n)?,
+                self.read_uc_limitn(n)?,
+                self.read_op_limitn(n)?,
+                self.read_ov_limitn(n)?,
+                self.read_uv_limitn(n)?,
+            ))
+        };
+        let limits = [limit(1)?, limit(2)?, limit(3)?, limit(4)?];
+        Ok(Config {
+            ctrl: self.read_ctrl()?,
+            neg_pwr_fsr: self.read_neg_pwr_fsr()?,
+            accum_config: self.read_accum_config()?,
+            slow: self.read_slow()?,
+            smbus_settings: self.read_smub_settings()?,
+            slow_alert1: self.read_slow_alert1()?,
+            gpio_alert2: self.read_gpio_alert2()?,
+            acc_fullness_limits: self.read_acc_fullness_limits()?,
+            oc_limits: limits.map(|l| l.0),
+            uc_limits: limits.map(|l| l.1),
+            op_limits: limits.map(|l| l.2),
+            ov_limits: limits.map(|l| l.3),
+            uv_limits: limits.map(|l| l.4),
+            oc_limit_n_samples: self.read_oc_limit_n_samples()?,
+            uc_limit_n_samples: self.read_uc_limit_n_samples()?,
+            op_limit_n_samples: self.read_op_limit_n_samples()?,
+            ov_limit_n_samples: self.read_ov_limit_n_samples()?,
+            uv_limit_n_samples: self.read_uv_limit_n_samples()?,
+            alert_enable: self.read_alert_enable()?,
+        })
+    }
+
+    /// Writes every register in `config` and issues a REFRESH to activate it
+    ///
+    /// ALERTs are disabled with a REFRESH_V while the limits are written to avoid false triggers
+    /// and the POR bit is cleared. BYTE_COUNT keeps the driver's current setting rather than the one in
+    /// `config`. The host must wait 1ms before reading the refreshed data.
+    pub fn write_config(&mut self, config: &Config) -> Result<(), Error<E>> {
+        self.apply_config(config, self.byte_count)
+    }
+
+    /// Same behavior as `write_config` but writes `byte_count` to [`SmbusSettings::byte_count`]
+    fn apply_config(&mut self, config: &Config, byte_count: bool) -> Result<(), Error<E>> {
+        self.write_alert_enable(AlertEnable::unpack(&[0; 3]).unwrap())?;
+        self.refresh_v()?;
+        self.write_ctrl(config.ctrl)?;
+        self.write_neg_pwr_fsr(config.neg_pwr_fsr)?;
+        self.write_accum_config(config.accum_config)?;
+        self.write_slow(config.slow)?;
+        self.write_smub_settings(SmbusSettings {
+            por: false,
+            byte_count,
+            ..config.smbus_settings
+        })?;
+        self.write_slow_alert1(config.slow_alert1)?;
+        self.write_gpio_alert2(config.gpio_alert2)?;
+        self.write_acc_fullness_limits(config.acc_fullness_limits)?;
+        for n in 1..=4 {
+            let i = (n - 1) as usize;
+            self.write_oc_limitn(config.oc_limits[i], n)?;
+            self.write_uc_limitn(config.uc_limits[i], n)?;
+            self.write_op_limitn(config.op_limits[i], n)?;
+            self.write_ov_limitn(config.ov_limits[i], n)?;
+            self.write_uv_limitn(config.uv_limits[i], n)?;
+        }
+        self.write_oc_limit_n_samples(config.oc_limit_n_samples)?;
+        self.write_uc_limit_n_samples(config.uc_limit_n_samples)?;
+        self.write_op_limit_n_samples(config.op_limit_n_samples)?;
+        self.write_ov_limit_n_samples(config.ov_limit_n_samples)?;
+        self.write_uv_limit_n_samples(config.uv_limit_n_samples)?;
+        self.write_alert_enable(config.alert_enable)?;
+        self.refresh()
+    }
+
+    /// Clears the POR bit in [`SmbusSettings`] so a later reset of the device can be detected
+    pub fn clear_por(&mut self) -> Result<(), Error<E>> {
+        self.modify_smub_settings(|settings| settings.por = false)
+    }
+
+    /// Enables POR monitoring
+    ///
+    /// `config` is applied to the device immediately (which clears the POR bit) and stored in the driver.
+    /// From then on, the high level measurement APIs check the POR bit before every read. If the device
+    /// has been reset, `config` is re-applied and the read fails with [`Error::PowerOnReset`] so the
+    /// caller knows the device state was lost. The host must wait 1ms before reading again.
+    pub fn monitor_por(&mut self, config: Config) -> Result<(), Error<E>> {
+        self.write_config(&config)?;
+        self.por_config = Some(config);
+        Ok(())
+    }
+
+    /// Disables POR monitoring, returning the stored configuration
+    pub fn stop_monitoring_por(&mut self) -> Option<Config> {
+        self.por_config.take()
+    }
+
+    /// Checks for a POR event when POR monitoring is enabled with [`Self::monitor_por`]
+    ///
+    /// If the POR bit is set, the stored configuration is re-applied and [`Error::PowerOnReset`] is returned.
+    /// SMBUS_SETTINGS is read without a block read since the reset clears BYTE_COUNT, which is restored to
+    /// the driver's setting along with the configuration.
+    /// Does nothing when monitoring is disabled.
+    pub fn check_por(&mut self) -> Result<(), Error<E>> {
+        let Some(config) = self.por_config else {
+            return Ok(());
+        };
+        // A block read would expect a byte count the reset may have turned off
+        self.send_byte(Address::SmbusSettings)?;
+        let settings = SmbusSettings::unpack(&[self.receive_byte()?]).unwrap();
+        if settings.por {
+            // Reads follow the device until BYTE_COUNT is restored
+            let byte_count = core::mem::replace(&mut self.byte_count, settings.byte_count);
+            self.apply_config(&config, byte_count)?;
+            return Err(Error::PowerOnReset);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AddrSelect;
+    use crate::sim::SimBus;
+
+    #[test]
+    fn reapplied_after_por() {
+        let bus = SimBus::new(&[0x10]);
+        let mut sensor = PAC194X::new(bus, AddrSelect::GND).unwrap();
+        let mut config = Config::default();
+        config.ov_limits[0].limit = 0x4000;
+        config.alert_enable.ch1_ov = true;
+        sensor.monitor_por(config).unwrap();
+        assert!(!sensor.read_smub_settings().unwrap().por);
+        assert_eq!(sensor.read_bus_voltage_uv_n(1), Ok(0));
+
+        // The device resets to its POR defaults
+        sensor.i2c.with(0x10, |dev| {
+            dev.set(Address::SmbusSettings, &[0x10]);
+            dev.set_n(Address::OvLimitn, 1, &[0x7F, 0xFF]);
+            dev.set(Address::AlertEnable, &[0; 3]);
+        });
+        assert_eq!(sensor.read_bus_voltage_uv_n(1), Err(Error::PowerOnReset));
+        let restored = sensor.read_config().unwrap();
+        assert!(!restored.smbus_settings.por);
+        assert_eq!(restored.ov_limits, config.ov_limits);
+        assert_eq!(restored.alert_enable, config.alert_enable);
+        // Every limit was written while the ALERTs were inactive
+        sensor.i2c.with(0x10, |dev| {
+            assert!(
+                dev.limit_writes()
+                    .iter()
+                    .all(|(_, enable)| enable == &[0; 3])
+            );
+        });
+        assert_eq!(sensor.read_bus_voltage_uv_n(1), Ok(0));
+    }
+
+    #[test]
+    fn byte_count_restored_after_por() {
+        let bus = SimBus::new(&[0x10]);
+        let mut sensor = PAC194X::new(bus, AddrSelect::GND).unwrap();
+        sensor
+            .i2c
+            .with(0x10, |dev| dev.set_n(Address::Vbusn, 1, &[0x80, 0x00]));
+        sensor.enable_byte_count(true).unwrap();
+        sensor.monitor_por(Config::default()).unwrap();
+        assert_eq!(sensor.read_bus_voltage_uv_n(1), Ok(4_500_000));
+
+        // The reset sets POR and clears BYTE_COUNT
+        sensor
+            .i2c
+            .with(0x10, |dev| dev.set(Address::SmbusSettings, &[0x10]));
+        assert_eq!(sensor.read_bus_voltage_uv_n(1), Err(Error::PowerOnReset));
+        let settings = sensor.read_smub_settings().unwrap();
+        assert!(settings.byte_count);
+        assert!(!settings.por);
+        assert_eq!(sensor.read_bus_voltage_uv_n(1), Ok(4_500_000));
+    }
+}
@@ -0,0 +1,748 @@
+//! Loading device and rail descriptions from TOML or JSON files
+//!
+//! A configuration file lists the devices on a bus, each with its part number, sample mode, ALERT pin
+//! functions and channels. Channels name their rail and describe the sense resistor, full scale ranges,
+//! accumulator mode, expected voltage and ALERT limits in volts, amps, watts and ohms. For example:
+//!
+//! ```toml
+//! [[device]]
+//! address = "GND"
+//! product = "PAC1944-1"
+//! sample_mode = "256"
+//! alert1 = "alert"
+//!
+//! [[device.channel]]
+//! channel = 1
+//! rail = "5V_MAIN"
+//! shunt = 0.010
+//! vsense_fsr = "bipolar"
+//! expected = [4.75, 5.25]
+//! alert = { ov = 5.5, uv = 4.5, oc = 3.0, samples = 4, route = ["alert1"] }
+//! ```
+//!
+//! [`ConfigFile::load`] validates the file against the capabilities of each part and produces a
+//! [`Config`] per device, ready to be applied with [`DeviceConfig::apply`], and a [`BoardBuf`] of the
+//! named rails.
+//!
+//! The accepted values are:
+//!
+//! - `address`: an [`AddrSelect`] name such as `"GND"`, `"499"` or `"VDD"`, or the 7-bit I2C address
+//! - `product`: the part number, such as `"PAC1954-2"`
+//! - `sample_mode`: `"1024_adaptive"`, `"256_adaptive"`, `"64_adaptive"`, `"8_adaptive"`, `"1024"`,
+//!   `"256"`, `"64"`, `"8"`, `"single_shot"`, `"single_shot_8x"`, `"fast"`, `"burst"` or `"sleep"`
+//! - `alert1`, `alert2`: the pin function, `"alert"`, `"input"`, `"output"` or `"slow"`
+//! - `vbus_fsr`, `vsense_fsr`: `"unipolar"`, `"bipolar"` or `"bipolar_half"`
+//! - `accumulate`: `"power"`, `"sense"` or `"bus"`
+//! - `samples`: the consecutive samples to trigger an ALERT, 1, 4, 8 or 16
+
+use crate::rail::{BoardBuf, BoardError, Rail};
+use crate::regs::*;
+use crate::{AddrSelect, Config, Error, PAC194X, ProductId, convert};
+use embedded_hal::i2c::I2c;
+use serde::Deserialize;
+use std::{fmt, fs, io, path::Path};
+
+/// The configuration of one device
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeviceConfig {
+    pub addr_sel: AddrSelect,
+    pub product_id: ProductId,
+    pub config: Config,
+}
+
+impl DeviceConfig {
+    /// Writes the configuration to `sensor`, which must be the described part at the described address.
+    ///
+    /// Returns [`Error::InvalidAddress`] if `sensor` is at another address and [`Error::ProductMismatch`]
+    /// if it is another part. See [`PAC194X::write_config`].
+    pub fn apply<E, I>(&self, sensor: &mut PAC194X<I>) -> Result<(), Error<E>>
+    where
+        I: I2c<Error = E>,
+    {
+        if sensor.address() != self.addr_sel as u8 {
+            return Err(Error::InvalidAddress);
+        }
+        if sensor.product_id != self.product_id {
+            return Err(Error::ProductMismatch(self.product_id));
+        }
+        sensor.write_config(&self.config)
+    }
+}
+
+/// A validated configuration file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigFile {
+    pub devices: Vec<DeviceConfig>,
+    /// The channels with a `rail` name
+    pub board: BoardBuf,
+}
+
+impl ConfigFile {
+    /// Loads a `.toml` or `.json` file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(LoadError::Io)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Self::from_toml(&text),
+            Some("json") => Self::from_json(&text),
+            _ => Err(LoadError::UnknownFormat),
+        }
+    }
+
+    /// Parses and validates a TOML configuration
+    pub fn from_toml(text: &str) -> Result<Self, LoadError> {
+        let spec: FileSpec = toml::from_str(text).map_err(LoadError::Toml)?;
+        spec.build().map_err(LoadError::Invalid)
+    }
+
+    /// Parses and validates a JSON configuration
+    pub fn from_json(text: &str) -> Result<Self, LoadError> {
+        let spec: FileSpec = serde_json::from_str(text).map_err(LoadError::Json)?;
+        spec.build().map_err(LoadError::Invalid)
+    }
+
+    /// The configuration of the device at `addr_sel`
+    pub fn device(&self, addr_sel: AddrSelect) -> Option<&DeviceConfig> {
+        self.devices.iter().find(|d| d.addr_sel == addr_sel)
+    }
+}
+
+/// Errors loading a configuration file
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    /// The file extension is neither `.toml` nor `.json`
+    UnknownFormat,
+    /// The file parsed but doesn't describe a valid configuration
+    Invalid(Invalid),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => e.fmt(f),
+            Self::Toml(e) => e.fmt(f),
+            Self::Json(e) => e.fmt(f),
+            Self::UnknownFormat => f.write_str("configuration files must be .toml or .json"),
+            Self::Invalid(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Problems with the contents of a configuration file
+#[derive(Debug, Clone, PartialEq)]
+pub enum Invalid {
+    /// Not an [`AddrSelect`] name or an address between 0x10 and 0x1F
+    Address(String),
+    /// Not a known part number
+    Product(String),
+    /// Two devices at the same address
+    DuplicateAddress(AddrSelect),
+    /// The part doesn't have the channel
+    Channel { addr_sel: AddrSelect, channel: u8 },
+    /// The channel is described twice
+    DuplicateChannel { addr_sel: AddrSelect, channel: u8 },
+    /// The channel has a rail name or current or power limits but no (positive) sense resistor value
+    Shunt { addr_sel: AddrSelect, channel: u8 },
+    /// The limit is outside of the full scale range of the channel
+    Limit {
+        addr_sel: AddrSelect,
+        channel: u8,
+        limit: &'static str,
+    },
+    /// Not 1, 4, 8 or 16 samples
+    Samples { addr_sel: AddrSelect, channel: u8 },
+    /// The expected voltage range of the rail doesn't fit in 32-bit microvolts
+    Expected { addr_sel: AddrSelect, channel: u8 },
+    /// ALERTs are routed to a pin that isn't configured as an ALERT pin
+    Route { addr_sel: AddrSelect, pin: u8 },
+    /// The rails are invalid, for example have duplicate names
+    Board(BoardError),
+}
+
+impl fmt::Display for Invalid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Address(a) => write!(f, "unknown address {a}"),
+            Self::Product(p) => write!(f, "unknown product {p}"),
+            Self::DuplicateAddress(a) => write!(f, "{a:?}: duplicate device"),
+            Self::Channel { addr_sel, channel } => {
+                write!(f, "{addr_sel:?}: the part has no channel {channel}")
+            }
+            Self::DuplicateChannel { addr_sel, channel } => {
+                write!(f, "{addr_sel:?}: duplicate channel {channel}")
+            }
+            Self::Shunt { addr_sel, channel } => {
+                write!(
+                    f,
+                    "{addr_sel:?}: channel {channel} needs a positive shunt value"
+                )
+            }
+            Self::Limit {
+                addr_sel,
+                channel,
+                limit,
+            } => write!(
+                f,
+                "{addr_sel:?}: the {limit} limit of channel {channel} is outside of its full scale range"
+            ),
+            Self::Samples { addr_sel, channel } => write!(
+                f,
+                "{addr_sel:?}: the samples of channel {channel} must be 1, 4, 8 or 16"
+            ),
+            Self::Expected { addr_sel, channel } => write!(
+                f,
+                "{addr_sel:?}: the expected range of channel {channel} is out of range"
+            ),
+            Self::Route { addr_sel, pin } => write!(
+                f,
+                "{addr_sel:?}: ALERTs are routed to alert{pin}, which isn't configured as an ALERT pin"
+            ),
+            Self::Board(e) => write!(f, "invalid rails: {e:?}"),
+        }
+    }
+}
+
+impl std::error::Error for Invalid {}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileSpec {
+    #[serde(default, rename = "device")]
+    devices: Vec<DeviceSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AddressSpec {
+    Name(String),
+    Raw(u8),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeviceSpec {
+    address: AddressSpec,
+    product: String,
+    sample_mode: Option<SampleModeSpec>,
+    alert1: Option<PinSpec>,
+    alert2: Option<PinSpec>,
+    #[serde(default, rename = "channel")]
+    channels: Vec<ChannelSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ChannelSpec {
+    channel: u8,
+    rail: Option<String>,
+    /// Ohms
+    shunt: Option<f64>,
+    #[serde(default = "enabled")]
+    enabled: bool,
+    vbus_fsr: Option<FsrSpec>,
+    vsense_fsr: Option<FsrSpec>,
+    accumulate: Option<AccumSpec>,
+    /// Volts
+    expected: Option<[f64; 2]>,
+    alert: Option<AlertSpec>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AlertSpec {
+    /// Amps
+    oc: Option<f64>,
+    /// Amps
+    uc: Option<f64>,
+    /// Watts
+    op: Option<f64>,
+    /// Volts
+    ov: Option<f64>,
+    /// Volts
+    uv: Option<f64>,
+    samples: Option<u8>,
+    #[serde(default)]
+    route: Vec<RouteSpec>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+enum SampleModeSpec {
+    #[serde(rename = "1024_adaptive")]
+    _1024Adaptive,
+    #[serde(rename = "256_adaptive")]
+    _256Adaptive,
+    #[serde(rename = "64_adaptive")]
+    _64Adaptive,
+    #[serde(rename = "8_adaptive")]
+    _8Adaptive,
+    #[serde(rename = "1024")]
+    _1024,
+    #[serde(rename = "256")]
+    _256,
+    #[serde(rename = "64")]
+    _64,
+    #[serde(rename = "8")]
+    _8,
+    #[serde(rename = "single_shot")]
+    SingleShot,
+    #[serde(rename = "single_shot_8x")]
+    SingleShot8X,
+    #[serde(rename = "fast")]
+    Fast,
+    #[serde(rename = "burst")]
+    Burst,
+    #[serde(rename = "sleep")]
+    Sleep,
+}
+
+impl From<SampleModeSpec> for SampleMode {
+    fn from(spec: SampleModeSpec) -> Self {
+        match spec {
+            SampleModeSpec::_1024Adaptive => Self::_1024Adaptive,
+            SampleModeSpec::_256Adaptive => Self::_256Adaptive,
+            SampleModeSpec::_64Adaptive => Self::_64Adaptive,
+            SampleModeSpec::_8Adaptive => Self::_8Adaptive,
+            SampleModeSpec::_1024 => Self::_1024,
+            SampleModeSpec::_256 => Self::_256,
+            SampleModeSpec::_64 => Self::_64,
+            SampleModeSpec::_8 => Self::_8,
+            SampleModeSpec::SingleShot => Self::SingleShot,
+            SampleModeSpec::SingleShot8X => Self::SingleShot8X,
+            SampleModeSpec::Fast => Self::Fast,
+            SampleModeSpec::Burst => Self::Burst,
+            SampleModeSpec::Sleep => Self::Sleep,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum PinSpec {
+    Alert,
+    Input,
+    Output,
+    Slow,
+}
+
+impl From<PinSpec> for GpioAlert {
+    fn from(spec: PinSpec) -> Self {
+        match spec {
+            PinSpec::Alert => Self::Alert,
+            PinSpec::Input => Self::Input,
+            PinSpec::Output => Self::Output,
+            PinSpec::Slow => Self::Slow,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum FsrSpec {
+    Unipolar,
+    Bipolar,
+    BipolarHalf,
+}
+
+impl From<FsrSpec> for VBusFSR {
+    fn from(spec: FsrSpec) -> Self {
+        match spec {
+            FsrSpec::Unipolar => Self::Unipolar,
+            FsrSpec::Bipolar => Self::BipolarHV,
+            FsrSpec::BipolarHalf => Self::BipolarLV,
+        }
+    }
+}
+
+impl From<FsrSpec> for VSenseFSR {
+    fn from(spec: FsrSpec) -> Self {
+        match spec {
+            FsrSpec::Unipolar => Self::Unipolar,
+            FsrSpec::Bipolar => Self::BipolarHV,
+            FsrSpec::BipolarHalf => Self::BipolarLV,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum AccumSpec {
+    Power,
+    Sense,
+    Bus,
+}
+
+impl From<AccumSpec> for AccumSetting {
+    fn from(spec: AccumSpec) -> Self {
+        match spec {
+            AccumSpec::Power => Self::VPower,
+            AccumSpec::Sense => Self::VSense,
+            AccumSpec::Bus => Self::VBus,
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum RouteSpec {
+    Alert1,
+    Alert2,
+}
+
+fn micro(value: f64) -> i64 {
+    (value * 1e6).round() as i64
+}
+
+fn parse_address(spec: &AddressSpec) -> Result<AddrSelect, Invalid> {
+    match spec {
+        AddressSpec::Raw(address) => {
+            AddrSelect::from_address(*address).ok_or(Invalid::Address(format!("{address:#04x}")))
+        }
+        AddressSpec::Name(name) => {
+            AddrSelect::from_name(name).ok_or_else(|| Invalid::Address(name.clone()))
+        }
+    }
+}
+
+impl FileSpec {
+    fn build(self) -> Result<ConfigFile, Invalid> {
+        let mut devices: Vec<DeviceConfig> = Vec::new();
+        let mut board = BoardBuf::new();
+        for spec in self.devices {
+            let device = spec.build(&mut board)?;
+            if devices.iter().any(|d| d.addr_sel == device.addr_sel) {
+                return Err(Invalid::DuplicateAddress(device.addr_sel));
+            }
+            devices.push(device);
+        }
+        board.as_board().check().map_err(Invalid::Board)?;
+        Ok(ConfigFile { devices, board })
+    }
+}
+
+impl DeviceSpec {
+    fn build(self, board: &mut BoardBuf) -> Result<DeviceConfig, Invalid> {
+        let addr_sel = parse_address(&self.address)?;
+        let product_id = ProductId::from_name(&self.product)
+            .ok_or_else(|| Invalid::Product(self.product.clone()))?;
+        let mut config = Config::default();
+        if let Some(mode) = self.sample_mode {
+            config.ctrl.sample_mode = mode.into();
+        }
+        if let Some(pin) = self.alert1 {
+            config.ctrl.slow_alert1 = pin.into();
+        }
+        if let Some(pin) = self.alert2 {
+            config.ctrl.gpio_alert2 = pin.into();
+        }
+
+        let mut seen = [false; 4];
+        for ch in self.channels {
+            let n = ch.channel;
+            if !(1..=product_id.channels()).contains(&n) {
+                return Err(Invalid::Channel {
+                    addr_sel,
+                    channel: n,
+                });
+            }
+            if core::mem::replace(&mut seen[n as usize - 1], true) {
+                return Err(Invalid::DuplicateChannel {
+                    addr_sel,
+                    channel: n,
+                });
+            }
+            ch.apply(addr_sel, product_id, &mut config, board)?;
+        }
+        Ok(DeviceConfig {
+            addr_sel,
+            product_id,
+            config,
+        })
+    }
+}
+
+impl ChannelSpec {
+    fn apply(
+        self,
+        addr_sel: AddrSelect,
+        product_id: ProductId,
+        config: &mut Config,
+        board: &mut BoardBuf,
+    ) -> Result<(), Invalid> {
+        let n = self.channel;
+        let i = n as usize - 1;
+        let vbus_fsr = self.vbus_fsr.map_or(VBusFSR::Unipolar, Into::into);
+        let vsense_fsr = self.vsense_fsr.map_or(VSenseFSR::Unipolar, Into::into);
+        config.neg_pwr_fsr.set_vbus_fsr(n, vbus_fsr);
+        config.neg_pwr_fsr.set_vsense_fsr(n, vsense_fsr);
+        config.ctrl.channel_n_off.set(n, !self.enabled);
+        if let Some(accumulate) = self.accumulate {
+            config.accum_config.set_accum(n, accumulate.into());
+        }
+
+        let shunt_uohm = self
+            .shunt
+            .map(micro)
+            .filter(|s| *s > 0 && *s <= u32::MAX as i64)
+            .map(|s| s as u32);
+        let no_shunt = Invalid::Shunt {
+            addr_sel,
+            channel: n,
+        };
+        if self.shunt.is_some() && shunt_uohm.is_none() {
+            return Err(no_shunt);
+        }
+
+        if let Some(rail) = self.rail {
+            let shunt_uohm = shunt_uohm.ok_or(no_shunt.clone())?;
+            let mut rail = Rail::new(rail, addr_sel, n, shunt_uohm).with_fsr(vbus_fsr, vsense_fsr);
+            if let Some([min, max]) = self.expected {
+                let uv = |v| {
+                    i32::try_from(micro(v)).map_err(|_| Invalid::Expected {
+                        addr_sel,
+                        channel: n,
+                    })
+                };
+                rail = rail.with_range(uv(min)?, uv(max)?);
+            }
+            board.push(rail);
+        }
+
+        let Some(alert) = self.alert else {
+            return Ok(());
+        };
+        let max_uv = product_id.max_voltage_uv();
+        let out_of_range = |limit| Invalid::Limit {
+            addr_sel,
+            channel: n,
+            limit,
+        };
+        let mut enabled = Vec::new();
+        if let Some(ov) = alert.ov {
+            let limit = convert::uv_to_vbus_limit(micro(ov), max_uv, vbus_fsr);
+            config.ov_limits[i].limit = limit.ok_or(out_of_range("OV"))?;
+            enabled.push(LimitAlert::Ov);
+        }
+        if let Some(uv) = alert.uv {
+            let limit = convert::uv_to_vbus_limit(micro(uv), max_uv, vbus_fsr);
+            config.uv_limits[i].limit = limit.ok_or(out_of_range("UV"))?;
+            enabled.push(LimitAlert::Uv);
+        }
+        for (value, limits, kind, name) in [
+            (
+                alert.oc,
+                &mut config.oc_limits[i].limit,
+                LimitAlert::Oc,
+                "OC",
+            ),
+            (
+                alert.uc,
+                &mut config.uc_limits[i].limit,
+                LimitAlert::Uc,
+                "UC",
+            ),
+        ] {
+            if let Some(value) = value {
+                let shunt_uohm = shunt_uohm.ok_or(no_shunt.clone())?;
+                let limit = convert::ua_to_vsense_limit(micro(value), vsense_fsr, shunt_uohm);
+                *limits = limit.ok_or(out_of_range(name))?;
+                enabled.push(kind);
+            }
+        }
+        if let Some(op) = alert.op {
+            let shunt_uohm = shunt_uohm.ok_or(no_shunt.clone())?;
+            let limit =
+                convert::uw_to_power_limit(micro(op), max_uv, vbus_fsr, vsense_fsr, shunt_uohm);
+            config.op_limits[i].limit = limit.ok_or(out_of_range("OP"))?;
+            enabled.push(LimitAlert::Op);
+        }
+
+        if let Some(samples) = alert.samples {
+            let count = SampleCount::from_samples(samples).ok_or(Invalid::Samples {
+                addr_sel,
+                channel: n,
+            })?;
+            config.oc_limit_n_samples.set_n_samples(n, count);
+            config.uc_limit_n_samples.set_n_samples(n, count);
+            config.op_limit_n_samples.set_n_samples(n, count);
+            config.ov_limit_n_samples.set_n_samples(n, count);
+            config.uv_limit_n_samples.set_n_samples(n, count);
+        }
+
+        for kind in &enabled {
+            config.alert_enable.set_limit(*kind, n, true);
+        }
+        for route in alert.route {
+            let (pin, function) = match route {
+                RouteSpec::Alert1 => (1, config.ctrl.slow_alert1),
+                RouteSpec::Alert2 => (2, config.ctrl.gpio_alert2),
+            };
+            if function != GpioAlert::Alert {
+                return Err(Invalid::Route { addr_sel, pin });
+            }
+            for kind in &enabled {
+                match route {
+                    RouteSpec::Alert1 => config.slow_alert1.set_limit(*kind, n, true),
+                    RouteSpec::Alert2 => config.gpio_alert2.set_limit(*kind, n, true),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimBus;
+
+    const TOML: &str = r#"
+        [[device]]
+        address = "GND"
+        product = "PAC1944-1"
+        sample_mode = "256"
+        alert1 = "alert"
+
+        [[device.channel]]
+        channel = 1
+        rail = "5V_MAIN"
+        shunt = 0.010
+        expected = [4.75, 5.25]
+        alert = { ov = 4.5, oc = 2.5, samples = 4, route = ["alert1"] }
+
+        [[device.channel]]
+        channel = 3
+        enabled = false
+        accumulate = "sense"
+        vbus_fsr = "bipolar_half"
+
+        [[device]]
+        address = 0x1F
+        product = "PAC1952_2"
+
+        [[device.channel]]
+        channel = 2
+        rail = "VCORE"
+        shunt = 0.002
+    "#;
+
+    #[test]
+    fn load_toml() {
+        let file = ConfigFile::from_toml(TOML).unwrap();
+        assert_eq!(file.devices.len(), 2);
+        let gnd = file.device(AddrSelect::GND).unwrap().config;
+        assert_eq!(gnd.ctrl.sample_mode, SampleMode::_256);
+        assert_eq!(gnd.ctrl.slow_alert1, GpioAlert::Alert);
+        assert!(gnd.ctrl.channel_n_off._3);
+        assert_eq!(gnd.accum_config.acc3_config, AccumSetting::VSense);
+        assert_eq!(gnd.neg_pwr_fsr.cfg_vb3, VBusFSR::BipolarLV);
+        assert_eq!(gnd.ov_limits[0].limit, 0x4000);
+        assert_eq!(gnd.oc_limits[0].limit, 0x2000);
+        assert_eq!(gnd.oc_limit_n_samples.n_samples_ch1, SampleCount::_4);
+        assert!(gnd.alert_enable.ch1_ov && gnd.alert_enable.ch1_oc);
+        assert!(!gnd.alert_enable.ch1_uv);
+        assert!(gnd.slow_alert1.ch1_ov && !gnd.gpio_alert2.ch1_ov);
+
+        let vdd = file.device(AddrSelect::VDD).unwrap();
+        assert_eq!(vdd.product_id, ProductId::PAC1952_2);
+        assert_eq!(vdd.config, Config::default());
+
+        let board = file.board.as_board();
+        assert_eq!(board.rail("5V_MAIN").unwrap().max_uv, 5_250_000);
+        assert_eq!(board.rail("VCORE").unwrap().shunt_uohm, 2_000);
+    }
+
+    #[test]
+    fn load_json() {
+        let file = ConfigFile::from_json(
+            r#"{"device": [{"address": "499", "product": "PAC1941-1",
+                "channel": [{"channel": 1, "rail": "VIN", "shunt": 0.1}]}]}"#,
+        )
+        .unwrap();
+        assert_eq!(file.devices[0].addr_sel, AddrSelect::_499);
+        assert_eq!(file.board.rails[0].name, "VIN");
+    }
+
+    #[test]
+    fn validation() {
+        let invalid = |toml: &str| match ConfigFile::from_toml(toml) {
+            Err(LoadError::Invalid(e)) => e,
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(
+            invalid(
+                "[[device]]\naddress = \"GND\"\nproduct = \"PAC1942-1\"\n[[device.channel]]\nchannel = 3"
+            ),
+            Invalid::Channel {
+                addr_sel: AddrSelect::GND,
+                channel: 3
+            }
+        );
+        assert_eq!(
+            invalid(
+                "[[device]]\naddress = \"GND\"\nproduct = \"PAC1944-1\"\n[[device.channel]]\nchannel = 1\nalert = { ov = 10.0 }"
+            ),
+            Invalid::Limit {
+                addr_sel: AddrSelect::GND,
+                channel: 1,
+                limit: "OV"
+            }
+        );
+        assert_eq!(
+            invalid(
+                "[[device]]\naddress = \"GND\"\nproduct = \"PAC1944-1\"\n[[device.channel]]\nchannel = 1\nalert = { oc = 1.0 }"
+            ),
+            Invalid::Shunt {
+                addr_sel: AddrSelect::GND,
+                channel: 1
+            }
+        );
+        assert_eq!(
+            invalid(
+                "[[device]]\naddress = \"GND\"\nproduct = \"PAC1944-1\"\n[[device.channel]]\nchannel = 1\nalert = { ov = 1.0, route = [\"alert2\"] }"
+            ),
+            Invalid::Route {
+                addr_sel: AddrSelect::GND,
+                pin: 2
+            }
+        );
+        assert_eq!(
+            invalid(
+                "[[device]]\naddress = \"GND\"\nproduct = \"PAC1944-1\"\n[[device.channel]]\nchannel = 1\nrail = \"VIN\"\nshunt = 0.01\nexpected = [0.0, 5000.0]"
+            ),
+            Invalid::Expected {
+                addr_sel: AddrSelect::GND,
+                channel: 1
+            }
+        );
+        assert_eq!(
+            invalid("[[device]]\naddress = 0x20\nproduct = \"PAC1944-1\""),
+            Invalid::Address("0x20".into())
+        );
+    }
+
+    #[test]
+    fn apply_checks_the_part() {
+        let file = ConfigFile::from_toml(TOML).unwrap();
+        let bus = SimBus::new(&[0x10, 0x1F]);
+        let mut gnd = PAC194X::new(bus.clone(), AddrSelect::GND).unwrap();
+        file.devices[0].apply(&mut gnd).unwrap();
+        assert_eq!(gnd.read_ctrl().unwrap().sample_mode, SampleMode::_256);
+        assert_eq!(gnd.read_ov_limitn(1).unwrap().limit, 0x4000);
+
+        let mut vdd = PAC194X::new(bus.clone(), AddrSelect::VDD).unwrap();
+        assert_eq!(
+            file.devices[1].apply(&mut vdd),
+            Err(Error::ProductMismatch(ProductId::PAC1952_2))
+        );
+        assert_eq!(file.devices[0].apply(&mut vdd), Err(Error::InvalidAddress));
+    }
+}
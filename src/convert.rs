@@ -0,0 +1,434 @@
+//! Conversions from raw register values to physical units
+//!
+//! The integer conversions work in microvolts, microamps, microwatts and microjoules and are rounded to
+//! the nearest integer (half away from zero), so they are usable on targets without an FPU. The `f32`
+//! conversions are only available with the `float` feature (enabled by default).
+
+use crate::regs::{VBusFSR, VSenseFSR};
+
+/// Full scale of the sense voltage in microvolts
+const VSENSE_MAX_UV: i128 = 100_000;
+
+/// Divides and rounds to the nearest integer, half away from zero
+fn div_round(num: i128, den: i128) -> i128 {
+    let half = den / 2;
+    if (num < 0) == (den < 0) {
+        (num + half) / den
+    } else {
+        (num - half) / den
+    }
+}
+
+/// Sign extends the lower `bits` bits of `raw`
+fn sign_extend(raw: u64, bits: u32) -> i64 {
+    let shift = 64 - bits;
+    ((raw << shift) as i64) >> shift
+}
+
+/// Interprets a 16-bit result register as signed if its range is bipolar
+fn signed_16(raw: u16, bipolar: bool) -> i128 {
+    if bipolar {
+        raw as i16 as i128
+    } else {
+        raw as i128
+    }
+}
+
+/// Returns the full scale bus voltage (in microvolts) and the result denominator for `fsr`
+fn vbus_scale(max_uv: u32, fsr: VBusFSR) -> (i128, i128) {
+    match fsr {
+        VBusFSR::Unipolar => (max_uv as i128, 1 << 16),
+        VBusFSR::BipolarHV => (max_uv as i128, 1 << 15),
+        VBusFSR::BipolarLV => (max_uv as i128 / 2, 1 << 15),
+    }
+}
+
+/// Returns the full scale sense voltage (in microvolts) and the result denominator for `fsr`
+fn vsense_scale(fsr: VSenseFSR) -> (i128, i128) {
+    match fsr {
+        VSenseFSR::Unipolar => (VSENSE_MAX_UV, 1 << 16),
+        VSenseFSR::BipolarHV => (VSENSE_MAX_UV, 1 << 15),
+        VSenseFSR::BipolarLV => (VSENSE_MAX_UV / 2, 1 << 15),
+    }
+}
+
+/// Returns the power full scale numerator (in µV²) and the denominator of a VPOWER or accumulator value
+///
+/// Power results are unsigned 2^30 fractions of full scale unless either measurement is bipolar, in
+/// which case they are signed 2^29 fractions.
+fn power_scale(max_uv: u32, vbus_fsr: VBusFSR, vsense_fsr: VSenseFSR) -> (i128, i128, bool) {
+    let bipolar = vbus_fsr != VBusFSR::Unipolar || vsense_fsr != VSenseFSR::Unipolar;
+    let vbus_fs = vbus_scale(max_uv, vbus_fsr).0;
+    let vsense_fs = vsense_scale(vsense_fsr).0;
+    let den = if bipolar { 1 << 29 } else { 1 << 30 };
+    (vbus_fs * vsense_fs, den, bipolar)
+}
+
+/// Converts a raw VBUS (or VBUS average) value to microvolts.
+///
+/// `max_uv` is the full scale bus voltage of the part, see [`crate::ProductId::max_voltage_uv`].
+pub fn vbus_to_uv(raw: u16, max_uv: u32, fsr: VBusFSR) -> i32 {
+    let (fs, den) = vbus_scale(max_uv, fsr);
+    div_round(signed_16(raw, fsr != VBusFSR::Unipolar) * fs, den) as i32
+}
+
+/// Converts a raw VSENSE (or VSENSE average) value to microvolts
+pub fn vsense_to_uv(raw: u16, fsr: VSenseFSR) -> i32 {
+    let (fs, den) = vsense_scale(fsr);
+    div_round(signed_16(raw, fsr != VSenseFSR::Unipolar) * fs, den) as i32
+}
+
+/// Converts a raw VSENSE (or VSENSE average) value to the current in microamps through a
+/// sense resistor of `shunt_uohm` micro-ohms
+pub fn vsense_to_ua(raw: u16, fsr: VSenseFSR, shunt_uohm: u32) -> i64 {
+    let (fs, den) = vsense_scale(fsr);
+    div_round(
+        signed_16(raw, fsr != VSenseFSR::Unipolar) * fs * 1_000_000,
+        den * shunt_uohm as i128,
+    ) as i64
+}
+
+/// Converts raw VBUS and VSENSE values (for example the rolling averages) to the power in microwatts
+/// through a sense resistor of `shunt_uohm` micro-ohms
+pub fn vbus_vsense_to_uw(
+    vbus_raw: u16,
+    vsense_raw: u16,
+    max_uv: u32,
+    vbus_fsr: VBusFSR,
+    vsense_fsr: VSenseFSR,
+    shunt_uohm: u32,
+) -> i64 {
+    let (vbus_fs, vbus_den) = vbus_scale(max_uv, vbus_fsr);
+    let (vsense_fs, vsense_den) = vsense_scale(vsense_fsr);
+    div_round(
+        signed_16(vbus_raw, vbus_fsr != VBusFSR::Unipolar)
+            * vbus_fs
+            * signed_16(vsense_raw, vsense_fsr != VSenseFSR::Unipolar)
+            * vsense_fs,
+        vbus_den * vsense_den * shunt_uohm as i128,
+    ) as i64
+}
+
+/// Converts a raw VPOWER value to microwatts through a sense resistor of `shunt_uohm` micro-ohms
+pub fn vpower_to_uw(
+    raw: u32,
+    max_uv: u32,
+    vbus_fsr: VBusFSR,
+    vsense_fsr: VSenseFSR,
+    shunt_uohm: u32,
+) -> i64 {
+    let (fs, den, bipolar) = power_scale(max_uv, vbus_fsr, vsense_fsr);
+    let raw = if bipolar {
+        sign_extend(raw as u64, 30) as i128
+    } else {
+        raw as i128
+    };
+    div_round(raw * fs, den * shunt_uohm as i128) as i64
+}
+
+/// Converts a raw accumulator value of accumulated VPOWER to microjoules through a sense resistor of
+/// `shunt_uohm` micro-ohms, where the accumulator is summed at `sample_rate` samples per second
+pub fn vacc_to_uj(
+    raw: u64,
+    max_uv: u32,
+    vbus_fsr: VBusFSR,
+    vsense_fsr: VSenseFSR,
+    shunt_uohm: u32,
+    sample_rate: u32,
+) -> i64 {
+    let (fs, den, bipolar) = power_scale(max_uv, vbus_fsr, vsense_fsr);
+    let raw = if bipolar {
+        sign_extend(raw, 56) as i128
+    } else {
+        raw as i128
+    };
+    div_round(raw * fs, den * shunt_uohm as i128 * sample_rate as i128) as i64
+}
+
+/// Converts a raw accumulator value of accumulated VSENSE to the charge in microcoulombs through a sense
+/// resistor of `shunt_uohm` micro-ohms, where the accumulator is summed at `sample_rate` samples per second
+pub fn vsense_acc_to_uc(raw: u64, fsr: VSenseFSR, shunt_uohm: u32, sample_rate: u32) -> i64 {
+    let (fs, den) = vsense_scale(fsr);
+    div_round(
+        signed_56(raw, fsr != VSenseFSR::Unipolar) * fs * 1_000_000,
+        den * shunt_uohm as i128 * sample_rate as i128,
+    ) as i64
+}
+
+/// Interprets a raw 56-bit accumulator value as signed if its range is bipolar
+pub(crate) fn signed_56(raw: u64, bipolar: bool) -> i128 {
+    if bipolar {
+        sign_extend(raw, 56) as i128
+    } else {
+        raw as i128
+    }
+}
+
+/// Converts a sum of raw VPOWER values taken at `sample_rate` samples per second, of any width, to
+/// nanojoules through a sense resistor of `shunt_uohm` micro-ohms
+pub fn power_sum_to_nj(
+    sum: i128,
+    max_uv: u32,
+    vbus_fsr: VBusFSR,
+    vsense_fsr: VSenseFSR,
+    shunt_uohm: u32,
+    sample_rate: u32,
+) -> i128 {
+    let (fs, den, _) = power_scale(max_uv, vbus_fsr, vsense_fsr);
+    div_round(
+        sum * fs * 1000,
+        den * shunt_uohm as i128 * sample_rate as i128,
+    )
+}
+
+/// Converts a sum of raw VSENSE values taken at `sample_rate` samples per second, of any width, to the
+/// charge in nanocoulombs through a sense resistor of `shunt_uohm` micro-ohms
+pub fn vsense_sum_to_nc(sum: i128, fsr: VSenseFSR, shunt_uohm: u32, sample_rate: u32) -> i128 {
+    let (fs, den) = vsense_scale(fsr);
+    div_round(
+        sum * fs * 1_000_000_000,
+        den * shunt_uohm as i128 * sample_rate as i128,
+    )
+}
+
+/// Converts a value to a 16-bit limit register value, `None` if it is outside of the full scale range.
+///
+/// Limits are 15 bits + sign fractions of full scale in every range, unipolar ranges included.
+fn limit_16(num: i128, den: i128) -> Option<i16> {
+    i16::try_from(div_round(num << 15, den)).ok()
+}
+
+/// Converts a bus voltage in microvolts to an OV/UV limit register value
+pub fn uv_to_vbus_limit(uv: i64, max_uv: u32, fsr: VBusFSR) -> Option<i16> {
+    limit_16(uv as i128, vbus_scale(max_uv, fsr).0)
+}
+
+/// Converts a current in microamps through a sense resistor of `shunt_uohm` micro-ohms to an OC/UC limit
+/// register value
+pub fn ua_to_vsense_limit(ua: i64, fsr: VSenseFSR, shunt_uohm: u32) -> Option<i16> {
+    limit_16(
+        ua as i128 * shunt_uohm as i128,
+        vsense_scale(fsr).0 * 1_000_000,
+    )
+}
+
+/// Converts a power in microwatts through a sense resistor of `shunt_uohm` micro-ohms to an OP limit
+/// register value, `None` if it is outside of the full scale range.
+///
+/// The limit is compared to the 24 MSBs of VPOWER, as 23 bits + sign in every range.
+pub fn uw_to_power_limit(
+    uw: i64,
+    max_uv: u32,
+    vbus_fsr: VBusFSR,
+    vsense_fsr: VSenseFSR,
+    shunt_uohm: u32,
+) -> Option<i32> {
+    let (fs, _, _) = power_scale(max_uv, vbus_fsr, vsense_fsr);
+    let limit = div_round((uw as i128 * shunt_uohm as i128) << 23, fs);
+    (-(1 << 23)..1 << 23)
+        .contains(&limit)
+        .then_some(limit as i32)
+}
+
+#[cfg(feature = "float")]
+pub(crate) fn vbus_to_real(raw: u16, max: f32, fsr: VBusFSR) -> f32 {
+    max * match fsr {
+        VBusFSR::Unipolar => (raw as f32) / 65536.0,
+        VBusFSR::BipolarHV => (raw as i16 as f32) / 32768.0,
+        VBusFSR::BipolarLV => (raw as i16 as f32) / 65536.0,
+    }
+}
+
+#[cfg(feature = "float")]
+pub(crate) fn vsense_to_real(raw: u16, fsr: VSenseFSR) -> f32 {
+    0.1 * match fsr {
+        VSenseFSR::Unipolar => (raw as f32) / 65536.0,
+        VSenseFSR::BipolarHV => (raw as i16 as f32) / 32768.0,
+        VSenseFSR::BipolarLV => (raw as i16 as f32) / 65536.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vbus_ranges() {
+        assert_eq!(vbus_to_uv(0xFFFF, 9_000_000, VBusFSR::Unipolar), 8_999_863);
+        assert_eq!(
+            vbus_to_uv(0x8000, 32_000_000, VBusFSR::Unipolar),
+            16_000_000
+        );
+        assert_eq!(
+            vbus_to_uv(0x8000, 9_000_000, VBusFSR::BipolarHV),
+            -9_000_000
+        );
+        assert_eq!(vbus_to_uv(0x7FFF, 9_000_000, VBusFSR::BipolarLV), 4_499_863);
+        assert_eq!(vbus_to_uv(0xFFFF, 9_000_000, VBusFSR::BipolarLV), -137);
+    }
+
+    #[test]
+    fn vsense_ranges() {
+        assert_eq!(vsense_to_uv(0x8000, VSenseFSR::Unipolar), 50_000);
+        assert_eq!(vsense_to_uv(0xC000, VSenseFSR::BipolarHV), -50_000);
+        assert_eq!(vsense_to_uv(0x4000, VSenseFSR::BipolarLV), 25_000);
+        // 50 mV across 10 mΩ
+        assert_eq!(vsense_to_ua(0x8000, VSenseFSR::Unipolar, 10_000), 5_000_000);
+        assert_eq!(
+            vsense_to_ua(0xC000, VSenseFSR::BipolarHV, 10_000),
+            -5_000_000
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "float")]
+    fn real_ranges() {
+        // Unipolar is unchanged: 0x8000 is half of 9 V and 100 mV
+        assert_eq!(vbus_to_real(0x8000, 9.0, VBusFSR::Unipolar), 4.5);
+        assert_eq!(vsense_to_real(0x8000, VSenseFSR::Unipolar), 0.05);
+        // ±9 V and ±100 mV are 15 bits + sign, 0x4000 is half of the range. Up to 0.1.3 the divisors
+        // of the bipolar ranges were swapped and these were 2.25 V, -2.25 V, 25 mV and -25 mV.
+        assert_eq!(vbus_to_real(0x4000, 9.0, VBusFSR::BipolarHV), 4.5);
+        assert_eq!(vbus_to_real(0xC000, 9.0, VBusFSR::BipolarHV), -4.5);
+        assert_eq!(vsense_to_real(0x4000, VSenseFSR::BipolarHV), 0.05);
+        assert_eq!(vsense_to_real(0xC000, VSenseFSR::BipolarHV), -0.05);
+        // ±4.5 V and ±50 mV, 0x4000 is a quarter of ±9 V and ±100 mV. These were 4.5 V, -4.5 V, 50 mV and
+        // -50 mV, twice the voltage.
+        assert_eq!(vbus_to_real(0x4000, 9.0, VBusFSR::BipolarLV), 2.25);
+        assert_eq!(vbus_to_real(0xC000, 9.0, VBusFSR::BipolarLV), -2.25);
+        assert_eq!(vsense_to_real(0x4000, VSenseFSR::BipolarLV), 0.025);
+        assert_eq!(vsense_to_real(0xC000, VSenseFSR::BipolarLV), -0.025);
+        // The integer conversions agree
+        for (raw, fsr) in [
+            (0x8000, VBusFSR::Unipolar),
+            (0xC000, VBusFSR::BipolarHV),
+            (0x4000, VBusFSR::BipolarLV),
+        ] {
+            assert_eq!(
+                (vbus_to_real(raw, 9.0, fsr) * 1e6) as i32,
+                vbus_to_uv(raw, 9_000_000, fsr)
+            );
+        }
+    }
+
+    #[test]
+    fn power_and_energy() {
+        // Half of 9 V * 100 mV / 10 mΩ = 90 W full scale
+        assert_eq!(
+            vpower_to_uw(
+                1 << 29,
+                9_000_000,
+                VBusFSR::Unipolar,
+                VSenseFSR::Unipolar,
+                10_000
+            ),
+            45_000_000
+        );
+        // Bipolar results are 29 bits + sign
+        assert_eq!(
+            vpower_to_uw(
+                0x3FFF_FFFF,
+                9_000_000,
+                VBusFSR::Unipolar,
+                VSenseFSR::BipolarHV,
+                10_000
+            ),
+            0
+        );
+        assert_eq!(
+            vpower_to_uw(
+                0x3000_0000,
+                9_000_000,
+                VBusFSR::Unipolar,
+                VSenseFSR::BipolarHV,
+                10_000
+            ),
+            -45_000_000
+        );
+        // 1024 samples of 45 W at 1024 SPS is 45 J
+        assert_eq!(
+            vacc_to_uj(
+                1024 << 29,
+                9_000_000,
+                VBusFSR::Unipolar,
+                VSenseFSR::Unipolar,
+                10_000,
+                1024
+            ),
+            45_000_000
+        );
+        // Wider than the accumulator: 2^32 samples of 45 W at 1024 SPS
+        assert_eq!(
+            power_sum_to_nj(
+                1 << 61,
+                9_000_000,
+                VBusFSR::Unipolar,
+                VSenseFSR::Unipolar,
+                10_000,
+                1024
+            ),
+            188_743_680_000_000_000
+        );
+    }
+
+    #[test]
+    fn charge() {
+        // 1024 samples of -2.5 A (a quarter of the bipolar range) at 1024 SPS is -2.5 C
+        let raw = (-1024i64 << 13) as u64 & ((1 << 56) - 1);
+        assert_eq!(
+            vsense_acc_to_uc(raw, VSenseFSR::BipolarHV, 10_000, 1024),
+            -2_500_000
+        );
+        assert_eq!(
+            vsense_sum_to_nc(1024 << 14, VSenseFSR::Unipolar, 10_000, 1024),
+            2_500_000_000
+        );
+    }
+
+    #[test]
+    fn limits() {
+        // Half of the 9 V unipolar range
+        assert_eq!(
+            uv_to_vbus_limit(4_500_000, 9_000_000, VBusFSR::Unipolar),
+            Some(0x4000)
+        );
+        assert_eq!(
+            uv_to_vbus_limit(-2_250_000, 9_000_000, VBusFSR::BipolarLV),
+            Some(-0x4000)
+        );
+        assert_eq!(
+            uv_to_vbus_limit(9_000_000, 9_000_000, VBusFSR::Unipolar),
+            None
+        );
+        // 2.5 A across 10 mΩ is a quarter of 100 mV
+        assert_eq!(
+            ua_to_vsense_limit(2_500_000, VSenseFSR::Unipolar, 10_000),
+            Some(0x2000)
+        );
+        assert_eq!(
+            ua_to_vsense_limit(10_000_000, VSenseFSR::Unipolar, 10_000),
+            None
+        );
+        // Half of 90 W
+        assert_eq!(
+            uw_to_power_limit(
+                45_000_000,
+                9_000_000,
+                VBusFSR::Unipolar,
+                VSenseFSR::Unipolar,
+                10_000
+            ),
+            Some(0x40_0000)
+        );
+        assert_eq!(
+            uw_to_power_limit(
+                90_000_000,
+                9_000_000,
+                VBusFSR::Unipolar,
+                VSenseFSR::Unipolar,
+                10_000
+            ),
+            None
+        );
+    }
+}
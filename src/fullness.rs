@@ -0,0 +1,347 @@
+//! Accumulator fullness ALERTs and overflow prediction
+//!
+//! The 56-bit accumulators and the 32-bit ACC_COUNT register fill up over time, and trip the `acc_ovf`
+//! and `acc_count` ALERTs when they reach the [`AccFullness`] level set in
+//! [`AccFullnessLimits`](crate::regs::AccFullnessLimits). How long that takes depends on the accumulation
+//! rate of the sample mode and active channels (see [`Timing`](crate::timing::Timing)), on what the
+//! accumulator accumulates ([`AccumSetting`]) and on whether its full scale range is bipolar, which halves
+//! the capacity of the accumulator along with the full scale of each sample.
+//!
+//! The times computed here for the accumulators are worst cases, with every sample at full scale, while
+//! ACC_COUNT fills at the same pace whatever is measured. [`PAC194X::predict_overflow`] instead
+//! extrapolates the actual contents of the registers.
+
+use crate::regs::{AccFullness, AccumSetting, AlertEnable, VBusFSR, VSenseFSR};
+use crate::{Error, PAC194X};
+use embedded_hal::i2c::I2c;
+
+/// An accumulating register with a fullness limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Accumulator {
+    /// The accumulator of channel `n` (1 to 4)
+    Channel(u8),
+    /// ACC_COUNT, shared by every channel
+    Count,
+}
+
+/// The levels of [`AccFullness`], emptiest first
+pub const LEVELS: [AccFullness; 4] = [
+    AccFullness::Partially,
+    AccFullness::Somewhat,
+    AccFullness::Mostly,
+    AccFullness::Full,
+];
+
+/// The fraction of full scale of `level` as a numerator and denominator
+pub fn fraction(level: AccFullness) -> (u8, u8) {
+    match level {
+        AccFullness::Full => (1, 1),
+        AccFullness::Mostly => (15, 16),
+        AccFullness::Somewhat => (7, 8),
+        AccFullness::Partially => (3, 4),
+    }
+}
+
+/// Whether the accumulator accumulates signed values with `setting` in these full scale ranges
+pub(crate) fn is_bipolar(setting: AccumSetting, vbus_fsr: VBusFSR, vsense_fsr: VSenseFSR) -> bool {
+    let vbus = vbus_fsr != VBusFSR::Unipolar;
+    let vsense = vsense_fsr != VSenseFSR::Unipolar;
+    match setting {
+        AccumSetting::VPower => vbus || vsense,
+        AccumSetting::VSense => vsense,
+        AccumSetting::VBus => vbus,
+    }
+}
+
+/// The capacity of the accumulator and the full scale of a sample, both in LSBs
+fn accumulator_scale(setting: AccumSetting, bipolar: bool) -> (u128, u128) {
+    // VPOWER samples are 30 bits, VSENSE and VBUS 16; bipolar values lose a bit to the sign
+    let sample_bits = match setting {
+        AccumSetting::VPower => 30,
+        AccumSetting::VSense | AccumSetting::VBus => 16,
+    };
+    let sign = bipolar as u32;
+    (1 << (56 - sign), 1 << (sample_bits - sign))
+}
+
+/// The time in microseconds to fill `amount` LSBs adding `per_sample` LSBs at `rate` samples per second
+fn fill_time_us(amount: u128, per_sample: u128, rate: u32) -> u64 {
+    let us = amount * 1_000_000 / (per_sample * rate as u128);
+    u64::try_from(us).unwrap_or(u64::MAX)
+}
+
+/// The worst-case time in microseconds for an accumulator to reach `level` at `rate` samples per second,
+/// with every sample at full scale
+pub fn accumulator_time_us(
+    setting: AccumSetting,
+    vbus_fsr: VBusFSR,
+    vsense_fsr: VSenseFSR,
+    rate: u32,
+    level: AccFullness,
+) -> u64 {
+    let bipolar = is_bipolar(setting, vbus_fsr, vsense_fsr);
+    time_to_level(rate, Some((setting, bipolar)), level)
+}
+
+/// The time in microseconds for ACC_COUNT to reach `level` at `rate` samples per second
+pub fn count_time_us(rate: u32, level: AccFullness) -> u64 {
+    let (num, den) = fraction(level);
+    fill_time_us((1 << 32) * num as u128 / den as u128, 1, rate)
+}
+
+/// A fullness limit and the time it takes to reach
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FullnessLimit {
+    pub level: AccFullness,
+    /// The time from empty to `level` in microseconds
+    pub time_us: u64,
+}
+
+/// The level whose time is nearest to `time_us`, given the time `time_to_level` of each level, the
+/// emptier one on a tie
+pub fn nearest_level(time_us: u64, time_to_level: impl Fn(AccFullness) -> u64) -> FullnessLimit {
+    LEVELS
+        .into_iter()
+        .map(|level| FullnessLimit {
+            level,
+            time_us: time_to_level(level),
+        })
+        .min_by_key(|limit| limit.time_us.abs_diff(time_us))
+        .unwrap()
+}
+
+/// The time left before an accumulator trips its fullness limit and before it overflows, extrapolated from
+/// its contents
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OverflowPrediction {
+    /// The limit set in [`AccFullnessLimits`](crate::regs::AccFullnessLimits)
+    pub limit: AccFullness,
+    /// Microseconds until the limit is reached, 0 if it already was
+    pub to_limit_us: u64,
+    /// Microseconds until the accumulator is full
+    pub to_overflow_us: u64,
+}
+
+/// How an accumulator fills: its setting and whether it is bipolar, `None` for ACC_COUNT
+type Filling = Option<(AccumSetting, bool)>;
+
+impl<E, I> PAC194X<I>
+where
+    I: I2c<Error = E>,
+{
+    /// The accumulation rate and how `acc` fills with the active settings
+    fn filling(&mut self, acc: Accumulator) -> Result<(u32, Filling), Error<E>> {
+        let rate = self.read_timing()?.acc_rate()?;
+        let Accumulator::Channel(n) = acc else {
+            return Ok((rate, None));
+        };
+        if !(1..=self.product_id.channels()).contains(&n) {
+            return Err(Error::NoSuchChannel(n));
+        }
+        let setting = self.read_accum_config_act()?.accum(n);
+        let fsr = self.read_neg_pwr_fsr_act()?;
+        let bipolar = is_bipolar(setting, fsr.vbus_fsr(n), fsr.vsense_fsr(n));
+        Ok((rate, Some((setting, bipolar))))
+    }
+
+    /// The worst-case time in microseconds for `acc` to reach `level` from empty with the active sample
+    /// mode, accumulator settings and full scale ranges
+    ///
+    /// Returns [`Error::NoSampleRate`] in a sample mode without a fixed rate.
+    pub fn time_to_fullness_us(
+        &mut self,
+        acc: Accumulator,
+        level: AccFullness,
+    ) -> Result<u64, Error<E>> {
+        let (rate, filling) = self.filling(acc)?;
+        Ok(time_to_level(rate, filling, level))
+    }
+
+    /// Sets the fullness limit of `acc` to the level reached nearest to `time_to_alert_us` microseconds
+    /// after the accumulators are reset, in the worst case, and enables its ALERT
+    ///
+    /// The ALERT of `acc` is disabled with a REFRESH_V while the limit changes to avoid false triggers.
+    /// Returns the level and its time.
+    pub fn set_fullness_limit(
+        &mut self,
+        acc: Accumulator,
+        time_to_alert_us: u64,
+    ) -> Result<FullnessLimit, Error<E>> {
+        let (rate, filling) = self.filling(acc)?;
+        let limit = nearest_level(time_to_alert_us, |level| {
+            time_to_level(rate, filling, level)
+        });
+
+        let mut enable = self.read_alert_enable()?;
+        *alert_flag(&mut enable, acc) = false;
+        self.write_alert_enable(enable)?;
+        self.refresh_v()?;
+        self.modify_acc_fullness_limits(|limits| match acc {
+            Accumulator::Channel(n) => limits.set_acc_full(n, limit.level),
+            Accumulator::Count => limits.acc_count_full = limit.level,
+        })?;
+        *alert_flag(&mut enable, acc) = true;
+        self.write_alert_enable(enable)?;
+        self.refresh_v()?;
+        Ok(limit)
+    }
+
+    /// Predicts when `acc` reaches its fullness limit and overflows, extrapolating the average rate it filled
+    /// at since the accumulators were reset
+    ///
+    /// Returns `None` while nothing has been accumulated and [`Error::ChannelDisabled`] for the
+    /// accumulator of a disabled channel. Read after a REFRESH_V, which keeps the accumulators running.
+    pub fn predict_overflow(
+        &mut self,
+        acc: Accumulator,
+    ) -> Result<Option<OverflowPrediction>, Error<E>> {
+        let (rate, filling) = self.filling(acc)?;
+        if let Accumulator::Channel(n) = acc {
+            self.check_enabled(n)?;
+        }
+        let limits = self.read_acc_fullness_limits()?;
+        let count = self.read_acc_count()?.count as u128;
+        let (limit, contents, capacity) = match (acc, filling) {
+            (Accumulator::Channel(n), Some((setting, bipolar))) => {
+                let raw = self.read_vaccn(n)?.sum;
+                let contents = if bipolar {
+                    ((raw << 8) as i64 >> 8).unsigned_abs() as u128
+                } else {
+                    raw as u128
+                };
+                let capacity = accumulator_scale(setting, bipolar).0;
+                (limits.acc_full(n), contents, capacity)
+            }
+            _ => (limits.acc_count_full, count, 1 << 32),
+        };
+        if count == 0 || contents == 0 {
+            return Ok(None);
+        }
+        // At the average of contents / count per sample so far
+        let time_to = |amount: u128| {
+            let left = amount.saturating_sub(contents);
+            fill_time_us(left * count, contents, rate)
+        };
+        let (num, den) = fraction(limit);
+        Ok(Some(OverflowPrediction {
+            limit,
+            to_limit_us: time_to(capacity * num as u128 / den as u128),
+            to_overflow_us: time_to(capacity),
+        }))
+    }
+}
+
+/// The enable of the fullness ALERT of `acc`
+fn alert_flag(enable: &mut AlertEnable, acc: Accumulator) -> &mut bool {
+    match acc {
+        Accumulator::Channel(_) => &mut enable.acc_ovf,
+        Accumulator::Count => &mut enable.acc_count,
+    }
+}
+
+/// The worst-case time to `level` of an accumulator filling at `rate`
+fn time_to_level(rate: u32, filling: Filling, level: AccFullness) -> u64 {
+    match filling {
+        Some((setting, bipolar)) => {
+            let (capacity, sample) = accumulator_scale(setting, bipolar);
+            let (num, den) = fraction(level);
+            fill_time_us(capacity * num as u128 / den as u128, sample, rate)
+        }
+        None => count_time_us(rate, level),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AddrSelect;
+    use crate::regs::{Address, SampleMode};
+    use crate::sim::SimBus;
+    use packed_struct::PackedStructSlice;
+
+    #[test]
+    fn worst_case_times() {
+        let unipolar = (VBusFSR::Unipolar, VSenseFSR::Unipolar);
+        // 2^56 / (2^30 * 1024) s
+        let power =
+            |level| accumulator_time_us(AccumSetting::VPower, unipolar.0, unipolar.1, 1024, level);
+        assert_eq!(power(AccFullness::Full), 65_536_000_000);
+        assert_eq!(power(AccFullness::Partially), 49_152_000_000);
+        // A bipolar range halves both the capacity and the full scale sample
+        assert_eq!(
+            accumulator_time_us(
+                AccumSetting::VPower,
+                VBusFSR::BipolarHV,
+                VSenseFSR::Unipolar,
+                1024,
+                AccFullness::Full
+            ),
+            65_536_000_000
+        );
+        // 2^32 / 1024 s
+        assert_eq!(count_time_us(1024, AccFullness::Full), 4_194_304_000_000);
+        assert_eq!(count_time_us(8, AccFullness::Mostly), 503_316_480_000_000);
+
+        let limit = nearest_level(59_000_000_000, power);
+        assert_eq!(limit.level, AccFullness::Somewhat);
+        assert_eq!(limit.time_us, 57_344_000_000);
+    }
+
+    #[test]
+    fn configure_and_predict() {
+        let bus = SimBus::new(&[0x10]);
+        let mut sensor = PAC194X::new(bus, AddrSelect::GND).unwrap();
+        let limit = sensor
+            .set_fullness_limit(Accumulator::Count, 3_700_000_000_000)
+            .unwrap();
+        assert_eq!(limit.level, AccFullness::Somewhat);
+        assert_eq!(
+            sensor.read_acc_fullness_limits().unwrap().acc_count_full,
+            AccFullness::Somewhat
+        );
+        assert!(sensor.read_alert_enable().unwrap().acc_count);
+        // Changed again while the ACC_COUNT ALERT is active
+        sensor
+            .set_fullness_limit(Accumulator::Count, 3_700_000_000_000)
+            .unwrap();
+        sensor.i2c.with(0x10, |dev| {
+            let writes = dev.limit_writes();
+            assert_eq!(writes.len(), 2);
+            for (_, enable) in writes {
+                assert!(!AlertEnable::unpack_from_slice(enable).unwrap().acc_count);
+            }
+        });
+        assert_eq!(
+            sensor.set_fullness_limit(Accumulator::Channel(5), 0),
+            Err(Error::NoSuchChannel(5))
+        );
+
+        assert_eq!(sensor.predict_overflow(Accumulator::Channel(1)), Ok(None));
+        // One second at 1024 SPS of VPOWER at a quarter of full scale
+        sensor.i2c.with(0x10, |dev| {
+            dev.set(Address::AccCount, &1024u32.to_be_bytes());
+            dev.set_n(Address::Vaccn, 1, &(1u64 << 38).to_be_bytes()[1..]);
+        });
+        let prediction = sensor
+            .predict_overflow(Accumulator::Channel(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(prediction.limit, AccFullness::Full);
+        assert_eq!(prediction.to_overflow_us, 262_143_000_000);
+        assert_eq!(prediction.to_limit_us, prediction.to_overflow_us);
+        let count = sensor
+            .predict_overflow(Accumulator::Count)
+            .unwrap()
+            .unwrap();
+        assert_eq!(count.limit, AccFullness::Somewhat);
+        assert_eq!(count.to_limit_us, 3_670_015_000_000);
+
+        sensor
+            .batch(|s| s.modify_ctrl(|ctrl| ctrl.sample_mode = SampleMode::SingleShot))
+            .unwrap();
+        assert_eq!(
+            sensor.time_to_fullness_us(Accumulator::Count, AccFullness::Full),
+            Err(Error::NoSampleRate(SampleMode::SingleShot))
+        );
+    }
+}
@@ -0,0 +1,427 @@
+//! Coulomb-counting battery fuel gauge
+//!
+//! A [`Gauge`] counts the charge through the sense resistor of one channel with its accumulator set to
+//! [`AccumSetting::VSense`] and a bipolar VSENSE range, so that charging and discharging currents
+//! accumulate with opposite signs. [`PAC194X::configure_gauge`] applies both settings and
+//! [`PAC194X::update_gauge`] harvests the accumulator through a [`Meter`], which keeps counting across the
+//! REFRESHes that keep the accumulator from overflowing.
+//!
+//! Each update splits the net charge since the previous update into charge in or charge out, so update
+//! often enough that the current rarely changes direction in between. Full cycles are counted from the
+//! charge out. A [`GaugeState`] holds everything that must survive a reboot and converts to and from
+//! [`GaugeState::SIZE`] bytes for EEPROM or flash.
+//!
+//! The gauge can also correct its state of charge from the open circuit voltage: when given a table of
+//! VBUS against state of charge, it looks the battery up once it has rested with a current below a
+//! threshold for long enough.
+//!
+//! ```
+//! use pac194x::gauge::{Gauge, GaugeConfig};
+//!
+//! // A 2000 mAh Li-ion cell on channel 1 through 10 mΩ, recalibrated after 30 minutes below 5 mA
+//! const OCV: [(u32, u16); 3] = [(3_300_000, 0), (3_700_000, 500), (4_200_000, 1000)];
+//! let config = GaugeConfig::new(1, 10_000, 2000).with_ocv(&OCV, 5_000, 30 * 60 * 1000);
+//! let gauge = Gauge::new(config);
+//! assert_eq!(gauge.soc_permille(), 1000);
+//! ```
+
+use crate::meter::Meter;
+use crate::regs::{AccumSetting, VSenseFSR};
+use crate::{Error, PAC194X};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+
+/// Nanocoulombs in a milliamp-hour
+const NC_PER_MAH: u64 = 3_600_000_000;
+
+/// How a [`Gauge`] is set up
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GaugeConfig<'a> {
+    /// The channel (1 to 4) of the battery
+    pub channel: u8,
+    pub shunt_uohm: u32,
+    pub capacity_mah: u32,
+    /// Whether a positive VSENSE charges the battery rather than discharging it
+    pub charge_positive: bool,
+    /// Open circuit VBUS in microvolts against state of charge in permille, by increasing voltage
+    pub ocv: &'a [(u32, u16)],
+    /// The current in microamps below which the battery is at rest
+    pub rest_ua: u32,
+    /// How long the battery must rest, in milliseconds, before its VBUS is used
+    pub rest_ms: u64,
+}
+
+impl<'a> GaugeConfig<'a> {
+    /// A battery of `capacity_mah` on `channel` (1 to 4) through a sense resistor of `shunt_uohm`
+    /// micro-ohms, discharged by a positive VSENSE, without voltage recalibration
+    pub fn new(channel: u8, shunt_uohm: u32, capacity_mah: u32) -> Self {
+        Self {
+            channel,
+            shunt_uohm,
+            capacity_mah,
+            charge_positive: false,
+            ocv: &[],
+            rest_ua: 0,
+            rest_ms: 0,
+        }
+    }
+
+    /// A positive VSENSE charges the battery
+    pub fn charge_positive(mut self) -> Self {
+        self.charge_positive = true;
+        self
+    }
+
+    /// Recalibrates from the open circuit voltage table `ocv` after `rest_ms` milliseconds below
+    /// `rest_ua` microamps
+    pub fn with_ocv(mut self, ocv: &'a [(u32, u16)], rest_ua: u32, rest_ms: u64) -> Self {
+        self.ocv = ocv;
+        self.rest_ua = rest_ua;
+        self.rest_ms = rest_ms;
+        self
+    }
+
+    fn capacity_nc(&self) -> i64 {
+        (self.capacity_mah as u64 * NC_PER_MAH) as i64
+    }
+}
+
+/// The state of a [`Gauge`] to save across reboots
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GaugeState {
+    /// The charge left in the battery in nanocoulombs
+    pub remaining_nc: i64,
+    /// The lifetime charge into the battery in nanocoulombs
+    pub charged_nc: u64,
+    /// The lifetime charge out of the battery in nanocoulombs
+    pub discharged_nc: u64,
+    /// The number of corrections from the open circuit voltage
+    pub recalibrations: u32,
+}
+
+impl GaugeState {
+    /// The size of the state as bytes
+    pub const SIZE: usize = 29;
+    /// The layout version of the bytes
+    const VERSION: u8 = 1;
+
+    /// The state as little-endian bytes, prefixed with a layout version
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[0] = Self::VERSION;
+        bytes[1..9].copy_from_slice(&self.remaining_nc.to_le_bytes());
+        bytes[9..17].copy_from_slice(&self.charged_nc.to_le_bytes());
+        bytes[17..25].copy_from_slice(&self.discharged_nc.to_le_bytes());
+        bytes[25..29].copy_from_slice(&self.recalibrations.to_le_bytes());
+        bytes
+    }
+
+    /// The state saved by [`to_bytes`](Self::to_bytes), `None` if the layout version doesn't match
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Option<Self> {
+        if bytes[0] != Self::VERSION {
+            return None;
+        }
+        Some(Self {
+            remaining_nc: i64::from_le_bytes(bytes[1..9].try_into().unwrap()),
+            charged_nc: u64::from_le_bytes(bytes[9..17].try_into().unwrap()),
+            discharged_nc: u64::from_le_bytes(bytes[17..25].try_into().unwrap()),
+            recalibrations: u32::from_le_bytes(bytes[25..29].try_into().unwrap()),
+        })
+    }
+}
+
+/// A coulomb-counting fuel gauge of one battery
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gauge<'a> {
+    config: GaugeConfig<'a>,
+    state: GaugeState,
+    meter: Meter,
+    /// The meter charge at the previous update, `None` until the first update
+    last_nc: Option<i128>,
+    last_ms: u64,
+    /// Since when the battery rests, and whether that rest was used yet
+    rest_since_ms: Option<u64>,
+    recalibrated: bool,
+}
+
+impl<'a> Gauge<'a> {
+    /// A gauge of a full battery
+    pub fn new(config: GaugeConfig<'a>) -> Self {
+        let state = GaugeState {
+            remaining_nc: config.capacity_nc(),
+            ..GaugeState::default()
+        };
+        Self::restore(config, state)
+    }
+
+    /// A gauge continuing from a saved `state`
+    ///
+    /// The charge between the save and the first update is not counted.
+    pub fn restore(config: GaugeConfig<'a>, state: GaugeState) -> Self {
+        assert!(
+            (1..=4).contains(&config.channel),
+            "Channel n must be between 1 and 4"
+        );
+        let mut shunts = [0; 4];
+        shunts[(config.channel - 1) as usize] = config.shunt_uohm;
+        Self {
+            config,
+            state,
+            meter: Meter::new(shunts),
+            last_nc: None,
+            last_ms: 0,
+            rest_since_ms: None,
+            recalibrated: false,
+        }
+    }
+
+    pub fn config(&self) -> &GaugeConfig<'a> {
+        &self.config
+    }
+
+    /// The state to save
+    pub fn state(&self) -> GaugeState {
+        self.state
+    }
+
+    /// The state of charge in permille
+    pub fn soc_permille(&self) -> u16 {
+        let capacity = self.config.capacity_nc();
+        if capacity == 0 {
+            return 0;
+        }
+        (self.state.remaining_nc.clamp(0, capacity) as i128 * 1000 / capacity as i128) as u16
+    }
+
+    /// The charge left in the battery in microamp-hours
+    pub fn remaining_uah(&self) -> i64 {
+        self.state.remaining_nc / (NC_PER_MAH / 1000) as i64
+    }
+
+    /// The lifetime charge into the battery in microcoulombs
+    pub fn charged_uc(&self) -> u64 {
+        self.state.charged_nc / 1000
+    }
+
+    /// The lifetime charge out of the battery in microcoulombs
+    pub fn discharged_uc(&self) -> u64 {
+        self.state.discharged_nc / 1000
+    }
+
+    /// The number of full cycles, as the charge out over the capacity
+    pub fn cycles(&self) -> u32 {
+        match self.config.capacity_nc() {
+            0 => 0,
+            capacity => (self.state.discharged_nc / capacity as u64) as u32,
+        }
+    }
+
+    /// Whether the battery has rested long enough for its open circuit voltage
+    pub fn is_at_rest(&self) -> bool {
+        self.rest_since_ms
+            .is_some_and(|since| self.last_ms.saturating_sub(since) >= self.config.rest_ms)
+    }
+
+    /// Adds the net charge `net_nc` (positive into the battery) measured since the previous update until
+    /// `now_ms`
+    fn count(&mut self, net_nc: i64, now_ms: u64) {
+        let capacity = self.config.capacity_nc();
+        if net_nc >= 0 {
+            self.state.charged_nc += net_nc as u64;
+        } else {
+            self.state.discharged_nc += net_nc.unsigned_abs();
+        }
+        // Past full or empty the battery doesn't hold more, and counting errors don't accumulate
+        self.state.remaining_nc = (self.state.remaining_nc + net_nc).clamp(0, capacity);
+
+        let dt_ms = now_ms.saturating_sub(self.last_ms).max(1);
+        // nC per ms are µA
+        let current_ua = net_nc.unsigned_abs() / dt_ms;
+        if current_ua < self.config.rest_ua as u64 {
+            self.rest_since_ms.get_or_insert(self.last_ms);
+        } else {
+            self.rest_since_ms = None;
+            self.recalibrated = false;
+        }
+        self.last_ms = now_ms;
+    }
+
+    /// Corrects the remaining charge from the open circuit voltage `vbus_uv`
+    fn recalibrate(&mut self, vbus_uv: i32) {
+        let Some(soc) = ocv_soc(self.config.ocv, vbus_uv.max(0) as u32) else {
+            return;
+        };
+        self.state.remaining_nc = self.config.capacity_nc() / 1000 * soc as i64;
+        self.state.recalibrations += 1;
+        self.recalibrated = true;
+    }
+}
+
+/// The state of charge in permille at `uv` interpolated in `table`, `None` for an empty table
+pub fn ocv_soc(table: &[(u32, u16)], uv: u32) -> Option<u16> {
+    let (first, last) = (table.first()?, table.last()?);
+    if uv <= first.0 {
+        return Some(first.1);
+    }
+    if uv >= last.0 {
+        return Some(last.1);
+    }
+    let i = table.iter().position(|(v, _)| *v > uv)?;
+    let ((v0, s0), (v1, s1)) = (table[i - 1], table[i]);
+    let soc = s0 as i64 + (s1 as i64 - s0 as i64) * (uv - v0) as i64 / (v1 - v0) as i64;
+    Some(soc as u16)
+}
+
+impl<E, I> PAC194X<I>
+where
+    I: I2c<Error = E>,
+{
+    /// Sets the accumulator of the battery channel to VSENSE and its VSENSE range to bipolar, unless it
+    /// already is, and applies both with a REFRESH
+    pub fn configure_gauge(&mut self, gauge: &Gauge) -> Result<(), Error<E>> {
+        let n = gauge.config.channel;
+        if !(1..=self.product_id.channels()).contains(&n) {
+            return Err(Error::NoSuchChannel(n));
+        }
+        self.batch(|s| {
+            s.modify_accum_config(|a| a.set_accum(n, AccumSetting::VSense))?;
+            if s.read_neg_pwr_fsr_act()?.vsense_fsr(n) == VSenseFSR::Unipolar {
+                s.modify_neg_pwr_fsr(|fsr| fsr.set_vsense_fsr(n, VSenseFSR::BipolarHV))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Harvests the accumulator into `gauge` at `now_ms` milliseconds, a monotonic time
+    ///
+    /// See [`harvest`](Self::harvest). The first update after [`Gauge::new`] or [`Gauge::restore`] only
+    /// starts counting. Once the battery is at rest with an open circuit voltage table, VBUS corrects the
+    /// remaining charge, once per rest. Returns [`Error::ChannelDisabled`] while the battery channel is
+    /// disabled, leaving the gauge as it was.
+    pub fn update_gauge<D: DelayNs>(
+        &mut self,
+        gauge: &mut Gauge,
+        now_ms: u64,
+        delay: &mut D,
+    ) -> Result<(), Error<E>> {
+        let n = gauge.config.channel;
+        self.check_enabled(n)?;
+        self.harvest(&mut gauge.meter, delay)?;
+        let total_nc = gauge.meter.totals(n).charge_nc;
+        let Some(last_nc) = gauge.last_nc.replace(total_nc) else {
+            gauge.last_ms = now_ms;
+            return Ok(());
+        };
+        let sensed_nc = (total_nc - last_nc) as i64;
+        let net_nc = if gauge.config.charge_positive {
+            sensed_nc
+        } else {
+            -sensed_nc
+        };
+        gauge.count(net_nc, now_ms);
+
+        if gauge.is_at_rest() && !gauge.recalibrated && !gauge.config.ocv.is_empty() {
+            let m = self.read_measurements()?;
+            if let Some(uv) = m.bus_voltage_uv(n) {
+                gauge.recalibrate(uv);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AddrSelect;
+    use crate::regs::Address;
+    use crate::sim::SimBus;
+    use embedded_hal_mock::eh1::delay::NoopDelay;
+
+    /// Sets ACC_COUNT and the VSENSE accumulator of channel 1 to `count` samples of 2.5 A, a quarter of
+    /// the bipolar range through 10 mΩ, in total
+    fn accumulate(sensor: &mut PAC194X<SimBus>, count: u32, quarters: i64) {
+        let vacc = (quarters << 13) as u64 & ((1 << 56) - 1);
+        sensor.i2c.with(0x10, |dev| {
+            dev.set(Address::AccCount, &count.to_be_bytes());
+            dev.set_n(Address::Vaccn, 1, &vacc.to_be_bytes()[1..]);
+        });
+    }
+
+    #[test]
+    fn count_charge() {
+        let bus = SimBus::new(&[0x10]);
+        let mut sensor = PAC194X::new(bus, AddrSelect::GND).unwrap();
+        const OCV: [(u32, u16); 2] = [(3_000_000, 0), (4_200_000, 1000)];
+        let config = GaugeConfig::new(1, 10_000, 1).with_ocv(&OCV, 1000, 60_000);
+        let mut gauge = Gauge::new(config);
+        sensor.configure_gauge(&gauge).unwrap();
+        assert_eq!(
+            sensor.read_accum_config_act().unwrap().accum(1),
+            AccumSetting::VSense
+        );
+        assert_eq!(
+            sensor.read_neg_pwr_fsr_act().unwrap().vsense_fsr(1),
+            VSenseFSR::BipolarHV
+        );
+
+        sensor.update_gauge(&mut gauge, 0, &mut NoopDelay).unwrap();
+        // 2.5 C out of 3.6 C in one second
+        accumulate(&mut sensor, 1024, 1024);
+        sensor
+            .update_gauge(&mut gauge, 1000, &mut NoopDelay)
+            .unwrap();
+        assert_eq!(gauge.discharged_uc(), 2_500_000);
+        assert_eq!(gauge.soc_permille(), 305);
+        assert_eq!(gauge.remaining_uah(), 305);
+        // 5 C back in, past full
+        accumulate(&mut sensor, 3072, -1024);
+        sensor
+            .update_gauge(&mut gauge, 3000, &mut NoopDelay)
+            .unwrap();
+        assert_eq!(gauge.charged_uc(), 5_000_000);
+        assert_eq!(gauge.soc_permille(), 1000);
+        assert_eq!(gauge.cycles(), 0);
+
+        // At rest at 3.6 V for a minute
+        sensor.i2c.with(0x10, |dev| {
+            dev.set_n(Address::Vbusn, 1, &0x6667u16.to_be_bytes())
+        });
+        sensor
+            .update_gauge(&mut gauge, 4000, &mut NoopDelay)
+            .unwrap();
+        assert!(!gauge.is_at_rest());
+        sensor
+            .update_gauge(&mut gauge, 64_000, &mut NoopDelay)
+            .unwrap();
+        assert!(gauge.is_at_rest());
+        assert_eq!(gauge.soc_permille(), 500);
+        assert_eq!(gauge.state().recalibrations, 1);
+
+        let saved = gauge.state().to_bytes();
+        let restored = Gauge::restore(config, GaugeState::from_bytes(&saved).unwrap());
+        assert_eq!(restored.state(), gauge.state());
+        assert_eq!(GaugeState::from_bytes(&[0; GaugeState::SIZE]), None);
+    }
+
+    #[test]
+    fn rest_with_time_going_back() {
+        let mut gauge = Gauge::new(GaugeConfig::new(1, 10_000, 1).with_ocv(&[], 1000, 1000));
+        gauge.count(-1_000_000, 1000);
+        gauge.count(0, 2000);
+        assert!(gauge.is_at_rest());
+        // An update stamped before the rest started, e.g. after the clock was reset
+        gauge.count(0, 500);
+        assert!(!gauge.is_at_rest());
+    }
+
+    #[test]
+    fn interpolate_ocv() {
+        let table = [(3_000_000, 0), (3_600_000, 400), (4_200_000, 1000)];
+        assert_eq!(ocv_soc(&table, 2_000_000), Some(0));
+        assert_eq!(ocv_soc(&table, 3_300_000), Some(200));
+        assert_eq!(ocv_soc(&table, 3_900_000), Some(700));
+        assert_eq!(ocv_soc(&table, 5_000_000), Some(1000));
+        assert_eq!(ocv_soc(&[], 3_900_000), None);
+    }
+}
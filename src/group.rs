@@ -0,0 +1,186 @@
+//! Several devices on one bus, sampled together
+//!
+//! A [`Group`] triggers every device with a single general call REFRESH_G so their results are latched
+//! at the same instant, then reads each of them. The resulting [`GroupMeasurements`] number the channels
+//! of all devices consecutively, so the group can be treated as one monitor with many channels.
+//!
+//! REFRESH_G is received by every PAC194X on the bus, including those that aren't part of the group, and
+//! resets their accumulators like REFRESH.
+
+use crate::measurements::{ChannelMeasurements, Measurements};
+use crate::{Error, PAC194X};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+
+/// `N` devices sharing a bus, each with its own handle to it (for example from `embedded-hal-bus`)
+pub struct Group<I, const N: usize>
+where
+    I: I2c,
+{
+    devices: [PAC194X<I>; N],
+}
+
+impl<E, I, const N: usize> Group<I, N>
+where
+    I: I2c<Error = E>,
+{
+    /// Groups `devices`, whose channels are numbered in the order given here
+    pub fn new(devices: [PAC194X<I>; N]) -> Self {
+        Self { devices }
+    }
+
+    /// Returns the devices of the group
+    pub fn destroy(self) -> [PAC194X<I>; N] {
+        self.devices
+    }
+
+    /// The device at `index` in the group
+    pub fn device(&mut self, index: usize) -> &mut PAC194X<I> {
+        &mut self.devices[index]
+    }
+
+    /// Refreshes every device on the bus at once with REFRESH_G.
+    ///
+    /// The host must wait 1ms before reading accumulator or Vbus/Vsense data.
+    pub fn refresh_g(&mut self) -> Result<(), Error<E>> {
+        match self.devices.first_mut() {
+            Some(first) => first.refresh_g(),
+            None => Ok(()),
+        }
+    }
+
+    /// Reads the results latched by the most recent refresh from every device
+    pub fn read_measurements(&mut self) -> Result<GroupMeasurements<N>, Error<E>> {
+        let mut devices = [None; N];
+        for (m, device) in devices.iter_mut().zip(self.devices.iter_mut()) {
+            *m = Some(device.read_measurements()?);
+        }
+        Ok(GroupMeasurements {
+            devices: devices.map(Option::unwrap),
+        })
+    }
+
+    /// Refreshes every device with REFRESH_G, waits for the results and reads them
+    pub fn snapshot<D: DelayNs>(
+        &mut self,
+        delay: &mut D,
+    ) -> Result<GroupMeasurements<N>, Error<E>> {
+        self.refresh_g()?;
+        delay.delay_ms(1);
+        self.read_measurements()
+    }
+}
+
+/// Time-aligned results of every device of a [`Group`].
+///
+/// Channels are numbered from 1 across the devices in group order, counting only the channels each part
+/// has. For example a PAC1944 followed by a PAC1942 give channels 1 to 6, where channel 5 is channel 1 of
+/// the PAC1942.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroupMeasurements<const N: usize> {
+    pub devices: [Measurements; N],
+}
+
+impl<const N: usize> GroupMeasurements<N> {
+    /// The total number of channels of the group
+    pub fn channels(&self) -> u16 {
+        self.devices
+            .iter()
+            .map(|m| m.product_id.channels() as u16)
+            .sum()
+    }
+
+    /// The index of the device and its channel number of group `channel`, `None` if it is out of range
+    pub fn locate(&self, channel: u16) -> Option<(usize, u8)> {
+        let mut first = 1;
+        for (i, m) in self.devices.iter().enumerate() {
+            let count = m.product_id.channels() as u16;
+            if (first..first + count).contains(&channel) {
+                return Some((i, (channel - first) as u8 + 1));
+            }
+            first += count;
+        }
+        None
+    }
+
+    /// The results of group `channel`, `None` if it is out of range or disabled
+    pub fn channel(&self, channel: u16) -> Option<&ChannelMeasurements> {
+        let (i, n) = self.locate(channel)?;
+        self.devices[i].channel(n)
+    }
+
+    /// The bus voltage of group `channel` in microvolts
+    pub fn bus_voltage_uv(&self, channel: u16) -> Option<i32> {
+        let (i, n) = self.locate(channel)?;
+        self.devices[i].bus_voltage_uv(n)
+    }
+
+    /// The sense voltage of group `channel` in microvolts
+    pub fn sense_voltage_uv(&self, channel: u16) -> Option<i32> {
+        let (i, n) = self.locate(channel)?;
+        self.devices[i].sense_voltage_uv(n)
+    }
+
+    /// The current of group `channel` in microamps, given the sense resistor value in micro-ohms
+    pub fn current_ua(&self, channel: u16, shunt_uohm: u32) -> Option<i64> {
+        let (i, n) = self.locate(channel)?;
+        self.devices[i].current_ua(n, shunt_uohm)
+    }
+
+    /// The power of group `channel` in microwatts, given the sense resistor value in micro-ohms
+    pub fn power_uw(&self, channel: u16, shunt_uohm: u32) -> Option<i64> {
+        let (i, n) = self.locate(channel)?;
+        self.devices[i].power_uw(n, shunt_uohm)
+    }
+
+    #[cfg(feature = "float")]
+    /// The bus voltage of group `channel`
+    pub fn bus_voltage(&self, channel: u16) -> Option<f32> {
+        let (i, n) = self.locate(channel)?;
+        self.devices[i].bus_voltage(n)
+    }
+
+    #[cfg(feature = "float")]
+    /// The sense voltage of group `channel`
+    pub fn sense_voltage(&self, channel: u16) -> Option<f32> {
+        let (i, n) = self.locate(channel)?;
+        self.devices[i].sense_voltage(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regs::Address;
+    use crate::retry::NoDelay;
+    use crate::sim::SimBus;
+    use crate::{AddrSelect, ProductId};
+
+    #[test]
+    fn snapshot_numbers_channels_across_devices() {
+        let bus = SimBus::new(&[0x10, 0x11]);
+        bus.with(0x10, |dev| dev.set_n(Address::Vbusn, 4, &[0x40, 0x00]));
+        bus.with(0x11, |dev| {
+            dev.set(Address::ProductId, &[ProductId::PAC1942_1 as u8]);
+            dev.set_n(Address::Vbusn, 2, &[0x80, 0x00]);
+            // Channel 1 is disabled but not yet active
+            dev.set(Address::Ctrl, &[0, 0x80]);
+        });
+        let mut group = Group::new([
+            PAC194X::new(bus.clone(), AddrSelect::GND).unwrap(),
+            PAC194X::new(bus.clone(), AddrSelect::_499).unwrap(),
+        ]);
+        // The disabled channel isn't active until the refresh
+        assert!(group.read_measurements().unwrap().channel(5).is_some());
+
+        let m = group.snapshot(&mut NoDelay).unwrap();
+        assert_eq!(m.channels(), 6);
+        assert_eq!(m.locate(4), Some((0, 4)));
+        assert_eq!(m.locate(6), Some((1, 2)));
+        assert_eq!(m.locate(7), None);
+        assert_eq!(m.bus_voltage_uv(4), Some(2_250_000));
+        // REFRESH_G latched the new CTRL on the second device too
+        assert!(m.channel(5).is_none());
+        assert_eq!(m.bus_voltage_uv(6), Some(4_500_000));
+    }
+}
@@ -2,7 +2,9 @@
 //!
 //! # Usage
 //!
-//! ```rust
+//! ```no_run
+//! # #[cfg(feature = "float")]
+//! # fn main() {
 //! use linux_embedded_hal::I2cdev;
 //! use pac194x::{PAC194X, AddrSelect};
 //!
@@ -16,23 +18,53 @@
 //!     println!("Channel 1 has a bus voltage of: {:.2} V", bus_voltage_1);
 //!     println!("Channel 1 is pulling a current of: {:.2} A", sense_voltage_1 / SENSE_RESISTOR);
 //! }
+//! # }
+//! # #[cfg(not(feature = "float"))]
+//! # fn main() {}
 //! ```
 //!
 //! # Reference
 //!
 //!- [Datasheet](https://ww1.microchip.com/downloads/en/DeviceDoc/PAC194X-Data-Sheet-20006543.pdf)
 
-#![cfg_attr(not(test), no_std)]
-
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
+
+pub mod alert;
+pub mod channels;
+pub mod config;
+#[cfg(feature = "config-file")]
+pub mod config_file;
+pub mod convert;
+pub mod fullness;
+pub mod gauge;
+pub mod group;
+#[cfg(feature = "std")]
+pub mod logger;
+pub mod measurements;
+pub mod meter;
+pub mod rail;
 pub mod regs;
+pub mod retry;
+pub mod scan;
+#[cfg(test)]
+mod sim;
+pub mod smbus;
+pub mod split;
+pub mod timing;
+
+pub use config::Config;
+pub use measurements::{ChannelMeasurements, Measurements};
 
 use embedded_hal::i2c::I2c;
 use packed_struct::prelude::*;
 use pastey::paste;
 use regs::*;
 
+#[cfg(feature = "float")]
+use convert::{vbus_to_real, vsense_to_real};
+
 #[repr(u8)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 /// Address select resistor value.
 ///
 /// `GND` is a zero-ohm resistor where `ADDRSEL` is connected to ground.
@@ -56,40 +88,157 @@ pub enum AddrSelect {
     VDD = 0b11111,
 }
 
+impl AddrSelect {
+    /// Every address select value, in order of I2C address
+    pub const ALL: [AddrSelect; 16] = [
+        Self::GND,
+        Self::_499,
+        Self::_806,
+        Self::_1270,
+        Self::_2050,
+        Self::_3240,
+        Self::_5230,
+        Self::_8450,
+        Self::_13300,
+        Self::_21500,
+        Self::_34000,
+        Self::_54900,
+        Self::_88700,
+        Self::_140000,
+        Self::_226000,
+        Self::VDD,
+    ];
+
+    /// The address select value of the 7-bit I2C `address`, `None` outside of 0x10 to 0x1F
+    pub fn from_address(address: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|a| *a as u8 == address)
+    }
+
+    /// The resistor value in ohms, or `GND` or `VDD`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::GND => "GND",
+            Self::_499 => "499",
+            Self::_806 => "806",
+            Self::_1270 => "1270",
+            Self::_2050 => "2050",
+            Self::_3240 => "3240",
+            Self::_5230 => "5230",
+            Self::_8450 => "8450",
+            Self::_13300 => "13300",
+            Self::_21500 => "21500",
+            Self::_34000 => "34000",
+            Self::_54900 => "54900",
+            Self::_88700 => "88700",
+            Self::_140000 => "140000",
+            Self::_226000 => "226000",
+            Self::VDD => "VDD",
+        }
+    }
+
+    /// The address select value of a [`name`](Self::name), which may also be written like the variant
+    /// (e.g. `_499`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name.strip_prefix('_').unwrap_or(name);
+        Self::ALL.into_iter().find(|a| a.name() == name)
+    }
+}
+
 /// The Product ID of the connected part
-#[derive(Debug, Copy, Clone)]
+#[repr(u8)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ProductId {
-    PAC1941_1,
-    PAC1942_1,
-    PAC1943_1,
-    PAC1944_1,
-    PAC1941_2,
-    PAC1942_2,
-    PAC1951_1,
-    PAC1952_1,
-    PAC1953_1,
-    PAC1954_1,
-    PAC1951_2,
-    PAC1952_2,
+    PAC1941_1 = 0b0110_1000,
+    PAC1942_1 = 0b0110_1001,
+    PAC1943_1 = 0b0110_1010,
+    PAC1944_1 = 0b0110_1011,
+    PAC1941_2 = 0b0110_1100,
+    PAC1942_2 = 0b0110_1101,
+    PAC1951_1 = 0b0111_1000,
+    PAC1952_1 = 0b0111_1001,
+    PAC1953_1 = 0b0111_1010,
+    PAC1954_1 = 0b0111_1011,
+    PAC1951_2 = 0b0111_1100,
+    PAC1952_2 = 0b0111_1101,
 }
 
 impl ProductId {
-    fn max_voltage(&self) -> f32 {
+    /// Decodes the value of the Product ID register, `None` if it isn't a known part
+    pub fn from_raw(raw: u8) -> Option<Self> {
+        Some(match raw {
+            0b0110_1000 => Self::PAC1941_1,
+            0b0110_1001 => Self::PAC1942_1,
+            0b0110_1010 => Self::PAC1943_1,
+            0b0110_1011 => Self::PAC1944_1,
+            0b0110_1100 => Self::PAC1941_2,
+            0b0110_1101 => Self::PAC1942_2,
+            0b0111_1000 => Self::PAC1951_1,
+            0b0111_1001 => Self::PAC1952_1,
+            0b0111_1010 => Self::PAC1953_1,
+            0b0111_1011 => Self::PAC1954_1,
+            0b0111_1100 => Self::PAC1951_2,
+            0b0111_1101 => Self::PAC1952_2,
+            _ => return None,
+        })
+    }
+
+    /// The part number, e.g. `PAC1944-1`
+    pub fn name(&self) -> &'static str {
         match self {
-            Self::PAC1941_1 => 9.0,
-            Self::PAC1942_1 => 9.0,
-            Self::PAC1943_1 => 9.0,
-            Self::PAC1944_1 => 9.0,
-            Self::PAC1941_2 => 9.0,
-            Self::PAC1942_2 => 9.0,
-            Self::PAC1951_1 => 32.0,
-            Self::PAC1952_1 => 32.0,
-            Self::PAC1953_1 => 32.0,
-            Self::PAC1954_1 => 32.0,
-            Self::PAC1951_2 => 32.0,
-            Self::PAC1952_2 => 32.0,
+            Self::PAC1941_1 => "PAC1941-1",
+            Self::PAC1942_1 => "PAC1942-1",
+            Self::PAC1943_1 => "PAC1943-1",
+            Self::PAC1944_1 => "PAC1944-1",
+            Self::PAC1941_2 => "PAC1941-2",
+            Self::PAC1942_2 => "PAC1942-2",
+            Self::PAC1951_1 => "PAC1951-1",
+            Self::PAC1952_1 => "PAC1952-1",
+            Self::PAC1953_1 => "PAC1953-1",
+            Self::PAC1954_1 => "PAC1954-1",
+            Self::PAC1951_2 => "PAC1951-2",
+            Self::PAC1952_2 => "PAC1952-2",
         }
     }
+
+    /// The part of a part number, which may also be written like the variant (e.g. `PAC1944_1`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        (0x68..=0x7D)
+            .filter_map(Self::from_raw)
+            .find(|p| p.name().split('-').eq(name.split(['-', '_'])))
+    }
+
+    /// The number of channels of the part
+    pub fn channels(&self) -> u8 {
+        match self {
+            Self::PAC1941_1 | Self::PAC1941_2 | Self::PAC1951_1 | Self::PAC1951_2 => 1,
+            Self::PAC1942_1 | Self::PAC1942_2 | Self::PAC1952_1 | Self::PAC1952_2 => 2,
+            Self::PAC1943_1 | Self::PAC1953_1 => 3,
+            Self::PAC1944_1 | Self::PAC1954_1 => 4,
+        }
+    }
+
+    /// The full scale bus voltage of the part in microvolts
+    pub fn max_voltage_uv(&self) -> u32 {
+        match self {
+            Self::PAC1941_1 => 9_000_000,
+            Self::PAC1942_1 => 9_000_000,
+            Self::PAC1943_1 => 9_000_000,
+            Self::PAC1944_1 => 9_000_000,
+            Self::PAC1941_2 => 9_000_000,
+            Self::PAC1942_2 => 9_000_000,
+            Self::PAC1951_1 => 32_000_000,
+            Self::PAC1952_1 => 32_000_000,
+            Self::PAC1953_1 => 32_000_000,
+            Self::PAC1954_1 => 32_000_000,
+            Self::PAC1951_2 => 32_000_000,
+            Self::PAC1952_2 => 32_000_000,
+        }
+    }
+
+    #[cfg(feature = "float")]
+    fn max_voltage(&self) -> f32 {
+        self.max_voltage_uv() as f32 / 1e6
+    }
 }
 
 /// A PAC194X power monitor on the I2C bus `I`.
@@ -100,15 +249,48 @@ where
     i2c: I,
     address: u8,
     product_id: ProductId,
+    por_config: Option<Config>,
+    /// Cached [`SmbusSettings::no_skip`], `None` until read back from the device
+    no_skip: Option<bool>,
+    /// Whether block reads are prefixed with a byte count, see [`SmbusSettings::byte_count`]
+    byte_count: bool,
 }
 
 /// Driver errors.
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum Error<E> {
     /// I2C bus error
     I2c(E),
     /// Errors such as overflowing the stack.
     Internal,
+    /// The I2C address is outside of the 0x10 to 0x1F range of the PAC194X
+    InvalidAddress,
+    /// The device was reset since the POR bit was last cleared.
+    /// The stored configuration has been re-applied.
+    PowerOnReset,
+    /// The byte count of an SMBus block read did not match the size of the register
+    InvalidByteCount,
+    /// A read with BYTE_COUNT set is longer than the 32 bytes of an SMBus block
+    BlockTooLong,
+    /// The Product ID register holds a value that isn't a known PAC194X or PAC195X part
+    UnknownProductId(u8),
+    /// The device isn't the part it was expected to be, holds the expected part
+    ProductMismatch(ProductId),
+    /// The part doesn't have the channel
+    NoSuchChannel(u8),
+    /// ALERTs are routed to a pin that isn't configured for the ALERT function in [`Ctrl`]
+    AlertPinMode(alert::AlertPin),
+    /// The sample mode has no fixed sample rate to convert a time to samples with
+    NoSampleRate(SampleMode),
+    /// A limit is outside of the full scale range of the channel
+    LimitOutOfRange,
+    /// The [`Board`](rail::Board) description is invalid
+    InvalidBoard(rail::BoardError),
+    /// The channel is disabled in [`CtrlAct`], so its results are stale
+    ChannelDisabled(u8),
+    /// [`CtrlAct`] didn't follow the channels written to [`Ctrl`], holds the channels that are off
+    ChannelsNotApplied(Channels),
 }
 
 macro_rules! read_fn {
@@ -167,10 +349,43 @@ macro_rules! write_n_fn {
     };
 }
 
+macro_rules! modify_fn {
+    ($var:ident: $type:ty) => {
+        paste! {
+            #[doc = stringify!(Reads the $type register, applies f to it and writes it back)]
+            pub fn [<modify_ $var>]<F>(&mut self, f: F) -> Result<(), Error<E>>
+            where
+                F: FnOnce(&mut $type),
+            {
+                let mut $var = self.[<read_ $var>]()?;
+                f(&mut $var);
+                self.[<write_ $var>]($var)
+            }
+        }
+    };
+}
+
+macro_rules! modify_n_fn {
+    ($var:ident: $type:ty) => {
+        paste! {
+            #[doc = stringify!(Reads the $type register of channel n, applies f to it and writes it back)]
+            pub fn [<modify_ $var>]<F>(&mut self, n: u8, f: F) -> Result<(), Error<E>>
+            where
+                F: FnOnce(&mut $type),
+            {
+                let mut $var = self.[<read_ $var>](n)?;
+                f(&mut $var);
+                self.[<write_ $var>]($var, n)
+            }
+        }
+    };
+}
+
 macro_rules! read_write {
     ($var:ident: $type:ty) => {
         write_fn!($var: $type);
         read_fn!($var: $type);
+        modify_fn!($var: $type);
     };
 }
 
@@ -178,25 +393,10 @@ macro_rules! read_write_n {
     ($var:ident: $type:ty) => {
         write_n_fn!($var: $type);
         read_n_fn!($var: $type);
+        modify_n_fn!($var: $type);
     };
 }
 
-fn vbus_to_real(raw: u16, max: f32, fsr: VBusFSR) -> f32 {
-    max * match fsr {
-        VBusFSR::Unipolar => (raw as f32) / 65536.0,
-        VBusFSR::BipolarHV => (i16::from_ne_bytes(raw.to_le_bytes()) as f32) / 65536.0,
-        VBusFSR::BipolarLV => (i16::from_ne_bytes(raw.to_le_bytes()) as f32) / 32768.0,
-    }
-}
-
-fn vsense_to_real(raw: u16, fsr: VSenseFSR) -> f32 {
-    0.1 * match fsr {
-        VSenseFSR::Unipolar => (raw as f32) / 65536.0,
-        VSenseFSR::BipolarHV => (i16::from_ne_bytes(raw.to_le_bytes()) as f32) / 65536.0,
-        VSenseFSR::BipolarLV => (i16::from_ne_bytes(raw.to_le_bytes()) as f32) / 32768.0,
-    }
-}
-
 impl<E, I> PAC194X<I>
 where
     I: I2c<Error = E>,
@@ -206,16 +406,54 @@ where
     /// This consumes the I2C bus `I`.
     /// To use this driver with other I2C crates, check out [shared-bus](https://github.com/Rahix/shared-bus)
     pub fn new(i2c: I, addr_sel: AddrSelect) -> Result<Self, Error<E>> {
-        let mut s = Self {
-            i2c,
-            address: addr_sel as u8,
-            // Default
-            product_id: ProductId::PAC1941_1,
-        };
+        Self::new_with_address(i2c, addr_sel as u8)
+    }
+
+    /// Initializes the driver for the device at the 7-bit I2C `address`, which must be between 0x10 and 0x1F
+    pub fn new_with_address(i2c: I, address: u8) -> Result<Self, Error<E>> {
+        if !(0x10..=0x1F).contains(&address) {
+            return Err(Error::InvalidAddress);
+        }
+        // The product ID is only a placeholder until it's read from the device
+        let mut s = Self::init(i2c, address, ProductId::PAC1941_1);
         s.product_id = s.product_id()?;
         Ok(s)
     }
 
+    /// Initializes the driver for a known part, without reading its product ID.
+    ///
+    /// No bus transactions are performed.
+    pub fn new_with_product_id(i2c: I, addr_sel: AddrSelect, product_id: ProductId) -> Self {
+        Self::init(i2c, addr_sel as u8, product_id)
+    }
+
+    fn init(i2c: I, address: u8, product_id: ProductId) -> Self {
+        Self {
+            i2c,
+            address,
+            product_id,
+            por_config: None,
+            no_skip: None,
+            byte_count: false,
+        }
+    }
+
+    /// Destroys the driver, returning the I2C bus
+    pub fn destroy(self) -> I {
+        self.i2c
+    }
+
+    /// The 7-bit I2C address of the device
+    pub fn address(&self) -> u8 {
+        self.address
+    }
+
+    /// Returns a reference to the I2C bus, for example to inspect the statistics of a
+    /// [`RetryI2c`](retry::RetryI2c)
+    pub fn bus(&self) -> &I {
+        &self.i2c
+    }
+
     /// The send byte protocol is used to set the internal address register pointer to the correct address
     /// location. No data is transferred.
     fn send_byte(&mut self, addr: Address) -> Result<(), Error<E>> {
@@ -236,7 +474,13 @@ where
     /// Block write is used to write multiple data bytes from a register that contains more than one byte of data
     /// of from a group of contiguous registers
     fn block_write(&mut self, bytes: &[u8]) -> Result<(), Error<E>> {
+        if bytes[0] == Address::SmbusSettings as u8 {
+            self.no_skip = None;
+        }
         self.i2c.write(self.address, bytes).map_err(Error::I2c)?;
+        if bytes[0] == Address::SmbusSettings as u8 {
+            self.byte_count = bytes[1] & 0b100 != 0;
+        }
         Ok(())
     }
 
@@ -244,19 +488,55 @@ where
     /// of contiguous registers
     fn block_read<const N: usize>(&mut self, addr: Address) -> Result<[u8; N], Error<E>> {
         let mut buf = [0u8; N];
-        self.i2c
-            .write_read(self.address, &[addr as u8], &mut buf)
-            .map_err(Error::I2c)?;
+        self.read_block(addr as u8, &mut buf)?;
         Ok(buf)
     }
 
+    /// Same behavior as `block_read` but reads into a buffer of runtime length starting at the raw address `addr`
+    fn block_read_into(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), Error<E>> {
+        self.read_block(addr, buf)
+    }
+
     /// Same behavior as `block_read` but adds the channel offset to the address
     fn block_read_n<const N: usize>(&mut self, addr: Address, n: u8) -> Result<[u8; N], Error<E>> {
         let mut buf = [0u8; N];
+        self.read_block((addr as u8) + (n - 1), &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Performs the block read, stripping and checking the byte count from the response if BYTE_COUNT is enabled
+    fn read_block(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), Error<E>> {
+        if !self.byte_count {
+            return self
+                .i2c
+                .write_read(self.address, &[addr], buf)
+                .map_err(Error::I2c);
+        }
+        if buf.len() > smbus::BLOCK_MAX {
+            return Err(Error::BlockTooLong);
+        }
+        let mut response = [0u8; smbus::BLOCK_MAX + 1];
+        let response = &mut response[..buf.len() + 1];
         self.i2c
-            .write_read(self.address, &[(addr as u8) + (n - 1)], &mut buf)
+            .write_read(self.address, &[addr], response)
             .map_err(Error::I2c)?;
-        Ok(buf)
+        if response[0] as usize != buf.len() {
+            return Err(Error::InvalidByteCount);
+        }
+        buf.copy_from_slice(&response[1..]);
+        Ok(())
+    }
+
+    /// Enables or disables the SMBus byte count in block read responses, see [`SmbusSettings::byte_count`].
+    ///
+    /// The settings are read with the receive byte protocol, so this works regardless of the current mode.
+    /// An [`SmbusI2c`](smbus::SmbusI2c) bus can only read with the byte count and refuses to disable it
+    /// with [`SmbusError::ByteCountRequired`](smbus::SmbusError::ByteCountRequired).
+    pub fn enable_byte_count(&mut self, enable: bool) -> Result<(), Error<E>> {
+        self.send_byte(Address::SmbusSettings)?;
+        let mut settings = SmbusSettings::unpack(&[self.receive_byte()?]).unwrap();
+        settings.byte_count = enable;
+        self.write_smub_settings(settings)
     }
 
     /// Refreshes the device
@@ -276,31 +556,51 @@ where
 
     /// Refreshes every PAC194X device on the bus by transmitting REFRESH_G to the
     /// general call address of 0
-    pub fn regresh_g(&mut self) -> Result<(), Error<E>> {
+    ///
+    /// Use a [`Group`](group::Group) to refresh several devices and read them as one.
+    pub fn refresh_g(&mut self) -> Result<(), Error<E>> {
         self.i2c
             .write(0u8, &[Address::RefreshG as u8])
             .map_err(Error::I2c)?;
         Ok(())
     }
 
+    #[deprecated(note = "renamed to `refresh_g`")]
+    /// Misspelled alias of [`refresh_g`](Self::refresh_g)
+    pub fn regresh_g(&mut self) -> Result<(), Error<E>> {
+        self.refresh_g()
+    }
+
+    /// Applies several register modifications and activates them with a single REFRESH
+    ///
+    /// Changes to [`Ctrl`], [`NegPwrFsr`], [`AccumConfig`] and [`AlertEnable`] only take effect after a
+    /// REFRESH command, so grouping them here avoids refreshing (and resetting the accumulators) once per change.
+    /// If `f` fails, no REFRESH is issued.
+    ///
+    /// ```no_run
+    /// # use linux_embedded_hal::I2cdev;
+    /// # use pac194x::{PAC194X, AddrSelect, regs::*};
+    /// # let mut sensor = PAC194X::new(I2cdev::new("/dev/i2c-1").unwrap(), AddrSelect::GND).unwrap();
+    /// sensor
+    ///     .batch(|s| {
+    ///         s.modify_ctrl(|ctrl| ctrl.sample_mode = SampleMode::_64)?;
+    ///         s.modify_neg_pwr_fsr(|fsr| fsr.cfg_vs2 = VSenseFSR::BipolarHV)
+    ///     })
+    ///     .unwrap();
+    /// ```
+    pub fn batch<F>(&mut self, f: F) -> Result<(), Error<E>>
+    where
+        F: FnOnce(&mut Self) -> Result<(), Error<E>>,
+    {
+        f(self)?;
+        self.refresh()
+    }
+
     /// Retrieves the Product ID of the connected component
     pub fn product_id(&mut self) -> Result<ProductId, Error<E>> {
         self.send_byte(regs::Address::ProductId)?;
-        Ok(match self.receive_byte()? {
-            0b0110_1000 => ProductId::PAC1941_1,
-            0b0110_1001 => ProductId::PAC1942_1,
-            0b0110_1010 => ProductId::PAC1943_1,
-            0b0110_1011 => ProductId::PAC1944_1,
-            0b0110_1100 => ProductId::PAC1941_2,
-            0b0110_1101 => ProductId::PAC1942_2,
-            0b0111_1000 => ProductId::PAC1951_1,
-            0b0111_1001 => ProductId::PAC1952_1,
-            0b0111_1010 => ProductId::PAC1953_1,
-            0b0111_1011 => ProductId::PAC1954_1,
-            0b0111_1100 => ProductId::PAC1951_2,
-            0b0111_1101 => ProductId::PAC1952_2,
-            _ => unreachable!(),
-        })
+        let raw = self.receive_byte()?;
+        ProductId::from_raw(raw).ok_or(Error::UnknownProductId(raw))
     }
 
     /// The Manufacturer ID register identifies Microchip as the manufacturer of the PAC194X.
@@ -317,17 +617,21 @@ where
         self.receive_byte()
     }
 
+    /// Reads the raw contents of the register at `addr` (and the following registers if `buf` is longer),
+    /// for example to dump the register map.
+    ///
+    /// Note that reading [`AlertStatus`] clears it.
+    pub fn read_register(&mut self, addr: u8, buf: &mut [u8]) -> Result<(), Error<E>> {
+        self.block_read_into(addr, buf)
+    }
+
+    #[cfg(feature = "float")]
     /// High level API for retrieving the bus voltage of channel `n`
     pub fn read_bus_voltage_n(&mut self, n: u8) -> Result<f32, Error<E>> {
         assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
-        let fsr_reg = self.read_neg_pwr_fsr_lat()?;
-        let fsr = match n {
-            1 => fsr_reg.cfg_vb1,
-            2 => fsr_reg.cfg_vb2,
-            3 => fsr_reg.cfg_vb3,
-            4 => fsr_reg.cfg_vb4,
-            _ => unreachable!(),
-        };
+        self.check_por()?;
+        self.check_enabled(n)?;
+        let fsr = self.read_neg_pwr_fsr_lat()?.vbus_fsr(n);
         Ok(vbus_to_real(
             self.read_vbusn(n)?.voltage,
             self.product_id.max_voltage(),
@@ -335,32 +639,24 @@ where
         ))
     }
 
+    #[cfg(feature = "float")]
     /// High level API for retrieving the sense voltage of channel `n`
     /// Use Ohm's law with your sense resistor value (V/R) to get the sense current
     pub fn read_sense_voltage_n(&mut self, n: u8) -> Result<f32, Error<E>> {
         assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
-        let fsr_reg = self.read_neg_pwr_fsr_lat()?;
-        let fsr = match n {
-            1 => fsr_reg.cfg_vs1,
-            2 => fsr_reg.cfg_vs2,
-            3 => fsr_reg.cfg_vs3,
-            4 => fsr_reg.cfg_vs4,
-            _ => unreachable!(),
-        };
+        self.check_por()?;
+        self.check_enabled(n)?;
+        let fsr = self.read_neg_pwr_fsr_lat()?.vsense_fsr(n);
         Ok(vsense_to_real(self.read_vsensen(n)?.voltage, fsr))
     }
 
+    #[cfg(feature = "float")]
     /// Same as [read_bus_voltage_n()], but using the accumulator-based rolling average
     pub fn read_avg_bus_voltage_n(&mut self, n: u8) -> Result<f32, Error<E>> {
         assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
-        let fsr_reg = self.read_neg_pwr_fsr_lat()?;
-        let fsr = match n {
-            1 => fsr_reg.cfg_vb1,
-            2 => fsr_reg.cfg_vb2,
-            3 => fsr_reg.cfg_vb3,
-            4 => fsr_reg.cfg_vb4,
-            _ => unreachable!(),
-        };
+        self.check_por()?;
+        self.check_enabled(n)?;
+        let fsr = self.read_neg_pwr_fsr_lat()?.vbus_fsr(n);
         Ok(vbus_to_real(
             self.read_vbusn_avg(n)?.voltage,
             self.product_id.max_voltage(),
@@ -368,18 +664,145 @@ where
         ))
     }
 
+    #[cfg(feature = "float")]
     /// Same as [read_sense_voltage_n()], but using the accumulator-based rolling average
     pub fn read_avg_sense_voltage_n(&mut self, n: u8) -> Result<f32, Error<E>> {
         assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
-        let fsr_reg = self.read_neg_pwr_fsr_lat()?;
-        let fsr = match n {
-            1 => fsr_reg.cfg_vs1,
-            2 => fsr_reg.cfg_vs2,
-            3 => fsr_reg.cfg_vs3,
-            4 => fsr_reg.cfg_vs4,
-            _ => unreachable!(),
-        };
-        Ok(vsense_to_real(self.read_vsensen(n)?.voltage, fsr))
+        self.check_por()?;
+        self.check_enabled(n)?;
+        let fsr = self.read_neg_pwr_fsr_lat()?.vsense_fsr(n);
+        Ok(vsense_to_real(self.read_vsensen_avg(n)?.voltage, fsr))
+    }
+
+    #[cfg(feature = "float")]
+    /// High level API for retrieving the rolling average current of channel `n`, given the sense resistor value in ohms
+    pub fn read_avg_current_n(&mut self, n: u8, shunt: f32) -> Result<f32, Error<E>> {
+        Ok(self.read_avg_sense_voltage_n(n)? / shunt)
+    }
+
+    #[cfg(feature = "float")]
+    /// High level API for retrieving the power of channel `n` from the rolling averages of the bus and sense
+    /// voltages, given the sense resistor value in ohms
+    pub fn read_avg_power_n(&mut self, n: u8, shunt: f32) -> Result<f32, Error<E>> {
+        let (vbus, vsense, fsr) = self.read_avgs_n(n)?;
+        Ok(
+            vbus_to_real(vbus.voltage, self.product_id.max_voltage(), fsr.vbus_fsr(n))
+                * vsense_to_real(vsense.voltage, fsr.vsense_fsr(n))
+                / shunt,
+        )
+    }
+
+    /// Integer-only API for retrieving the rolling average current of channel `n` in microamps, given the
+    /// sense resistor value in micro-ohms
+    pub fn read_avg_current_ua_n(&mut self, n: u8, shunt_uohm: u32) -> Result<i64, Error<E>> {
+        assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
+        self.check_por()?;
+        self.check_enabled(n)?;
+        let fsr = self.read_neg_pwr_fsr_lat()?.vsense_fsr(n);
+        Ok(convert::vsense_to_ua(
+            self.read_vsensen_avg(n)?.voltage,
+            fsr,
+            shunt_uohm,
+        ))
+    }
+
+    /// Integer-only API for retrieving the power of channel `n` in microwatts from the rolling averages
+    /// of the bus and sense voltages, given the sense resistor value in micro-ohms
+    pub fn read_avg_power_uw_n(&mut self, n: u8, shunt_uohm: u32) -> Result<i64, Error<E>> {
+        let (vbus, vsense, fsr) = self.read_avgs_n(n)?;
+        Ok(convert::vbus_vsense_to_uw(
+            vbus.voltage,
+            vsense.voltage,
+            self.product_id.max_voltage_uv(),
+            fsr.vbus_fsr(n),
+            fsr.vsense_fsr(n),
+            shunt_uohm,
+        ))
+    }
+
+    /// Reads the rolling averages of channel `n` along with the FSR they were measured with
+    fn read_avgs_n(&mut self, n: u8) -> Result<(VbusnAvg, VsensenAvg, NegPwrFsrLat), Error<E>> {
+        assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
+        self.check_por()?;
+        self.check_enabled(n)?;
+        let fsr = self.read_neg_pwr_fsr_lat()?;
+        Ok((self.read_vbusn_avg(n)?, self.read_vsensen_avg(n)?, fsr))
+    }
+
+    /// Integer-only API for retrieving the bus voltage of channel `n` in microvolts
+    pub fn read_bus_voltage_uv_n(&mut self, n: u8) -> Result<i32, Error<E>> {
+        assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
+        self.check_por()?;
+        self.check_enabled(n)?;
+        let fsr = self.read_neg_pwr_fsr_lat()?.vbus_fsr(n);
+        Ok(convert::vbus_to_uv(
+            self.read_vbusn(n)?.voltage,
+            self.product_id.max_voltage_uv(),
+            fsr,
+        ))
+    }
+
+    /// Integer-only API for retrieving the sense voltage of channel `n` in microvolts
+    pub fn read_sense_voltage_uv_n(&mut self, n: u8) -> Result<i32, Error<E>> {
+        assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
+        self.check_por()?;
+        self.check_enabled(n)?;
+        let fsr = self.read_neg_pwr_fsr_lat()?.vsense_fsr(n);
+        Ok(convert::vsense_to_uv(self.read_vsensen(n)?.voltage, fsr))
+    }
+
+    /// Integer-only API for retrieving the current of channel `n` in microamps, given the sense
+    /// resistor value in micro-ohms
+    pub fn read_current_ua_n(&mut self, n: u8, shunt_uohm: u32) -> Result<i64, Error<E>> {
+        assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
+        self.check_por()?;
+        self.check_enabled(n)?;
+        let fsr = self.read_neg_pwr_fsr_lat()?.vsense_fsr(n);
+        Ok(convert::vsense_to_ua(
+            self.read_vsensen(n)?.voltage,
+            fsr,
+            shunt_uohm,
+        ))
+    }
+
+    /// Integer-only API for retrieving the power of channel `n` in microwatts, given the sense
+    /// resistor value in micro-ohms
+    pub fn read_power_uw_n(&mut self, n: u8, shunt_uohm: u32) -> Result<i64, Error<E>> {
+        assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
+        self.check_por()?;
+        self.check_enabled(n)?;
+        let fsr = self.read_neg_pwr_fsr_lat()?;
+        Ok(convert::vpower_to_uw(
+            self.read_vpowern(n)?.power,
+            self.product_id.max_voltage_uv(),
+            fsr.vbus_fsr(n),
+            fsr.vsense_fsr(n),
+            shunt_uohm,
+        ))
+    }
+
+    /// Integer-only API for retrieving the energy accumulated by channel `n` since the last REFRESH in
+    /// microjoules, given the sense resistor value in micro-ohms and the accumulation rate in samples per second
+    ///
+    /// The accumulator of channel `n` must be configured for [`AccumSetting::VPower`] (the default).
+    pub fn read_energy_uj_n(
+        &mut self,
+        n: u8,
+        shunt_uohm: u32,
+        sample_rate: u32,
+    ) -> Result<i64, Error<E>> {
+        assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
+        self.check_por()?;
+        self.check_enabled(n)?;
+        let fsr = self.read_neg_pwr_fsr_lat()?;
+        Ok(convert::vacc_to_uj(
+            self.read_vaccn(n)?.sum,
+            self.product_id.max_voltage_uv(),
+            fsr.vbus_fsr(n),
+            fsr.vsense_fsr(n),
+            shunt_uohm,
+            sample_rate,
+        ))
     }
 
     // Auto generated functions for reading and writing all of our registers
@@ -393,7 +816,7 @@ where
     read_n_fn!(vpowern: Vpowern);
     read_write!(smub_settings: SmbusSettings);
     read_write!(neg_pwr_fsr: NegPwrFsr);
-    read_fn!(slow: Slow);
+    read_write!(slow: Slow);
     read_fn!(ctrl_act: CtrlAct);
     read_write!(neg_pwr_fsr_act: NegPwrFsrAct);
     read_fn!(ctrl_lat: CtrlLat);
@@ -421,9 +844,87 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use embedded_hal_mock::eh1::i2c::{Mock as I2cMock, Transaction as I2cTransaction};
+
+    const ADDR: u8 = AddrSelect::GND as u8;
+
+    /// Expectations for the product ID read in `new`, identifying a PAC1944-1
+    fn identify() -> Vec<I2cTransaction> {
+        vec![
+            I2cTransaction::write(ADDR, vec![0xFD]),
+            I2cTransaction::read(ADDR, vec![0b0110_1011]),
+        ]
+    }
+
     #[test]
     fn addr_values() {
         assert_eq!(Address::AlertEnable as u8, 0x49);
         assert_eq!(Address::RevisionId as u8, 0xFF);
     }
+
+    #[test]
+    fn raw_address_range() {
+        let mut i2c = I2cMock::new(&[]);
+        assert_eq!(
+            PAC194X::new_with_address(i2c.clone(), 0x20).err(),
+            Some(Error::InvalidAddress)
+        );
+        i2c.done();
+
+        let mut i2c = I2cMock::new(&[
+            I2cTransaction::write(0x1F, vec![0xFD]),
+            I2cTransaction::read(0x1F, vec![0b0111_1000]),
+        ]);
+        let sensor = PAC194X::new_with_address(i2c.clone(), 0x1F).unwrap();
+        assert_eq!(sensor.address(), AddrSelect::VDD as u8);
+        i2c.done();
+    }
+
+    #[test]
+    fn known_product_id_and_release() {
+        let sensor =
+            PAC194X::new_with_product_id(I2cMock::new(&[]), AddrSelect::_806, ProductId::PAC1952_2);
+        sensor.destroy().done();
+    }
+
+    #[test]
+    #[cfg(feature = "float")]
+    fn avg_sense_voltage_reads_avg_register() {
+        let mut expectations = identify();
+        expectations.extend([
+            // CTRL_ACT with every channel on
+            I2cTransaction::write_read(ADDR, vec![0x21], vec![0x00, 0x00]),
+            // NEG_PWR_FSR_LAT, all unipolar
+            I2cTransaction::write_read(ADDR, vec![0x24], vec![0x00, 0x00]),
+            // VSENSE3_AVG
+            I2cTransaction::write_read(ADDR, vec![0x15], vec![0x80, 0x00]),
+        ]);
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = PAC194X::new(i2c.clone(), AddrSelect::GND).unwrap();
+        assert_eq!(sensor.read_avg_sense_voltage_n(3).unwrap(), 0.05);
+        i2c.done();
+    }
+
+    #[test]
+    fn avg_current_and_power() {
+        let mut expectations = identify();
+        expectations.extend([
+            I2cTransaction::write_read(ADDR, vec![0x21], vec![0x00, 0x00]),
+            // Channel 1 VSENSE bipolar, VBUS unipolar
+            I2cTransaction::write_read(ADDR, vec![0x24], vec![0b0100_0000, 0x00]),
+            I2cTransaction::write_read(ADDR, vec![0x13], vec![0xC0, 0x00]),
+            I2cTransaction::write_read(ADDR, vec![0x21], vec![0x00, 0x00]),
+            I2cTransaction::write_read(ADDR, vec![0x24], vec![0b0100_0000, 0x00]),
+            // VBUS1_AVG and VSENSE1_AVG
+            I2cTransaction::write_read(ADDR, vec![0x0F], vec![0x80, 0x00]),
+            I2cTransaction::write_read(ADDR, vec![0x13], vec![0xC0, 0x00]),
+        ]);
+        let mut i2c = I2cMock::new(&expectations);
+        let mut sensor = PAC194X::new(i2c.clone(), AddrSelect::GND).unwrap();
+        // -50 mV across 10 mΩ
+        assert_eq!(sensor.read_avg_current_ua_n(1, 10_000).unwrap(), -5_000_000);
+        // 4.5 V * -5 A
+        assert_eq!(sensor.read_avg_power_uw_n(1, 10_000).unwrap(), -22_500_000);
+        i2c.done();
+    }
 }
@@ -0,0 +1,445 @@
+//! Logging rail readings to CSV or JSON Lines files
+//!
+//! A [`Logger`] writes one record per rail of a [`BoardBuf`] each time it is given the measurements of
+//! the devices. A record holds the time, the bus voltage, current and power of the rail and the energy
+//! it delivered since logging started, in volts, amps, watts and joules. Files can be rotated by size
+//! or age, and [`Logger::run`] samples at a fixed interval until a stop flag is set, such as from a
+//! Ctrl-C handler, and flushes before returning.
+//!
+//! Energy is integrated from the accumulators of the devices, which sum VPOWER on every conversion: the
+//! change of VACC over the change of ACC_COUNT between two records is the average power over that
+//! time, including the samples the logger didn't see. The accumulators must be in
+//! [`AccumSetting::VPower`](crate::regs::AccumSetting::VPower) mode (the default) and must not be reset
+//! by a REFRESH while logging, so sample with REFRESH_V. If the accumulator was reset or didn't advance,
+//! the instantaneous power is used instead.
+
+use crate::convert;
+use crate::measurements::Measurements;
+use crate::rail::{BoardBuf, Rail};
+use std::fmt::{self, Write as _};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// VACC is 56 bits wide
+const VACC_MASK: u64 = (1 << 56) - 1;
+
+const CSV_HEADER: &str = "time,rail,address,channel,bus_voltage,current,power,energy\n";
+
+/// The file format of a log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Comma separated values with a header line in every file
+    Csv,
+    /// One JSON object per line
+    JsonLines,
+}
+
+impl Format {
+    /// The format for the extension of `path`: `.csv`, or `.jsonl` or `.ndjson`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(Self::Csv),
+            "jsonl" | "ndjson" => Some(Self::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+/// When to start a new file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rotation {
+    /// Write a single file
+    Never,
+    /// Once the records in the file take at least this many bytes
+    Size(u64),
+    /// Once the file is this old
+    Age(Duration),
+}
+
+/// A failure while logging
+#[derive(Debug)]
+pub enum LogError<E> {
+    /// Sampling the devices failed
+    Device(E),
+    /// Writing the log failed
+    Io(io::Error),
+}
+
+impl<E> From<io::Error> for LogError<E> {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for LogError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Device(e) => write!(f, "sampling failed: {e:?}"),
+            Self::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for LogError<E> {}
+
+/// The energy delivered by a rail
+#[derive(Debug, Clone, Copy, Default)]
+struct Energy {
+    /// VACC and ACC_COUNT of the previous record
+    last: Option<(u64, u32)>,
+    /// In picojoules, microwatts times microseconds
+    pj: i128,
+}
+
+/// The file being written
+#[derive(Debug)]
+struct Output {
+    path: PathBuf,
+    rotation: Rotation,
+    format: Format,
+    index: u32,
+    file: BufWriter<File>,
+    /// Bytes of records written to the file
+    written: u64,
+    opened: Instant,
+}
+
+impl Output {
+    fn open(path: PathBuf, rotation: Rotation, format: Format) -> io::Result<Self> {
+        let mut output = Self {
+            file: BufWriter::new(File::create(numbered(&path, rotation, 0))?),
+            path,
+            rotation,
+            format,
+            index: 0,
+            written: 0,
+            opened: Instant::now(),
+        };
+        output.start()?;
+        Ok(output)
+    }
+
+    fn current(&self) -> PathBuf {
+        numbered(&self.path, self.rotation, self.index)
+    }
+
+    fn start(&mut self) -> io::Result<()> {
+        if self.format == Format::Csv {
+            self.file.write_all(CSV_HEADER.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, s: &str) -> io::Result<()> {
+        self.file.write_all(s.as_bytes())?;
+        self.written += s.len() as u64;
+        Ok(())
+    }
+
+    /// Starts the next file if the current one is due for rotation
+    fn rotate(&mut self) -> io::Result<()> {
+        let due = match self.rotation {
+            Rotation::Never => false,
+            Rotation::Size(size) => self.written >= size,
+            Rotation::Age(age) => self.opened.elapsed() >= age,
+        };
+        if !due {
+            return Ok(());
+        }
+        self.file.flush()?;
+        self.index += 1;
+        self.file = BufWriter::new(File::create(self.current())?);
+        self.written = 0;
+        self.opened = Instant::now();
+        self.start()
+    }
+}
+
+/// `path` itself without rotation, otherwise `path` with `-NNNN` appended to the file stem
+fn numbered(path: &Path, rotation: Rotation, index: u32) -> PathBuf {
+    if rotation == Rotation::Never {
+        return path.to_path_buf();
+    }
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(ext) => format!("{stem}-{index:04}.{}", ext.to_string_lossy()),
+        None => format!("{stem}-{index:04}"),
+    };
+    path.with_file_name(name)
+}
+
+/// Formats a value in millionths with six decimals, without rounding through a float
+fn decimal(micro: i64) -> String {
+    let sign = if micro < 0 { "-" } else { "" };
+    let abs = micro.unsigned_abs();
+    format!("{sign}{}.{:06}", abs / 1_000_000, abs % 1_000_000)
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Writes rail readings to rotating CSV or JSON Lines files
+#[derive(Debug)]
+pub struct Logger {
+    board: BoardBuf,
+    output: Output,
+    energy: Vec<Energy>,
+    last_time: Option<SystemTime>,
+}
+
+impl Logger {
+    /// Creates a log of every rail of `board` at `path`.
+    ///
+    /// With rotation, the files are numbered: `rails.csv` is written as `rails-0000.csv`, `rails-0001.csv`
+    /// and so on.
+    pub fn create(
+        path: impl Into<PathBuf>,
+        format: Format,
+        rotation: Rotation,
+        board: BoardBuf,
+    ) -> io::Result<Self> {
+        Ok(Self {
+            energy: vec![Energy::default(); board.rails.len()],
+            output: Output::open(path.into(), rotation, format)?,
+            board,
+            last_time: None,
+        })
+    }
+
+    /// The rails being logged
+    pub fn board(&self) -> &BoardBuf {
+        &self.board
+    }
+
+    /// The file being written
+    pub fn path(&self) -> PathBuf {
+        self.output.current()
+    }
+
+    /// The energy delivered by the rail at `index` of the board since logging started, in microjoules
+    pub fn energy_uj(&self, index: usize) -> Option<i64> {
+        Some((self.energy.get(index)?.pj / 1_000_000) as i64)
+    }
+
+    /// Writes a record for every rail with results in `measurements`, taken at `time`
+    pub fn log(&mut self, time: SystemTime, measurements: &[Measurements]) -> io::Result<()> {
+        let elapsed_us = self
+            .last_time
+            .and_then(|last| time.duration_since(last).ok())
+            .map_or(0, |dt| dt.as_micros() as i128);
+        self.last_time = Some(time);
+        let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let time = format!(
+            "{}.{:06}",
+            since_epoch.as_secs(),
+            since_epoch.subsec_micros()
+        );
+
+        self.output.rotate()?;
+        for (rail, energy) in self.board.rails.iter().zip(&mut self.energy) {
+            let Some(m) = measurements
+                .iter()
+                .find(|m| m.address == rail.addr_sel as u8)
+            else {
+                continue;
+            };
+            let n = rail.channel;
+            let (Some(ch), Some(bus_uv), Some(current_ua), Some(power_uw)) = (
+                m.channel(n),
+                m.bus_voltage_uv(n),
+                m.current_ua(n, rail.shunt_uohm),
+                m.power_uw(n, rail.shunt_uohm),
+            ) else {
+                continue;
+            };
+            let sample = (ch.vacc.sum, m.acc_count.count);
+            let average_uw = energy
+                .last
+                .and_then(|last| average_power_uw(rail, m, last, sample));
+            energy.pj += average_uw.unwrap_or(power_uw) as i128 * elapsed_us;
+            energy.last = Some(sample);
+
+            let energy_uj = (energy.pj / 1_000_000) as i64;
+            let record = match self.output.format {
+                Format::Csv => format!(
+                    "{time},{},{:#04x},{n},{},{},{},{}\n",
+                    csv_field(&rail.name),
+                    m.address,
+                    decimal(bus_uv as i64),
+                    decimal(current_ua),
+                    decimal(power_uw),
+                    decimal(energy_uj),
+                ),
+                Format::JsonLines => format!(
+                    "{{\"time\":{time},\"rail\":{},\"address\":\"{:#04x}\",\"channel\":{n},\"bus_voltage\":{},\"current\":{},\"power\":{},\"energy\":{}}}\n",
+                    json_string(&rail.name),
+                    m.address,
+                    decimal(bus_uv as i64),
+                    decimal(current_ua),
+                    decimal(power_uw),
+                    decimal(energy_uj),
+                ),
+            };
+            self.output.write(&record)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the buffered records to the file
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.output.file.flush()
+    }
+
+    /// Logs the measurements returned by `sample` every `interval` until `stop` is set, then flushes.
+    ///
+    /// `sample` should REFRESH_V (or REFRESH_G) the devices, wait for the results to latch and read them.
+    /// If sampling takes longer than the interval, the next sample is taken right away.
+    pub fn run<F, E>(
+        &mut self,
+        interval: Duration,
+        stop: &AtomicBool,
+        mut sample: F,
+    ) -> Result<(), LogError<E>>
+    where
+        F: FnMut() -> Result<Vec<Measurements>, E>,
+    {
+        let mut next = Instant::now();
+        let result = loop {
+            if stop.load(Ordering::Relaxed) {
+                break Ok(());
+            }
+            let measurements = match sample() {
+                Ok(m) => m,
+                Err(e) => break Err(LogError::Device(e)),
+            };
+            if let Err(e) = self.log(SystemTime::now(), &measurements) {
+                break Err(LogError::Io(e));
+            }
+            next = (next + interval).max(Instant::now());
+            // Sleep in short steps to notice the stop flag
+            while let Some(left) = next.checked_duration_since(Instant::now()) {
+                if stop.load(Ordering::Relaxed) {
+                    break;
+                }
+                thread::sleep(left.min(Duration::from_millis(100)));
+            }
+        };
+        self.flush()?;
+        result
+    }
+}
+
+/// The average power of a rail between two accumulator readings, `None` if the accumulator was reset or
+/// didn't advance
+fn average_power_uw<S>(
+    rail: &Rail<S>,
+    m: &Measurements,
+    (last_vacc, last_count): (u64, u32),
+    (vacc, count): (u64, u32),
+) -> Option<i64> {
+    let samples = count.checked_sub(last_count).filter(|s| *s > 0)?;
+    let n = rail.channel;
+    // At one sample per second the accumulated energy in microjoules is the sum in microwatts
+    let sum_uw = convert::vacc_to_uj(
+        vacc.wrapping_sub(last_vacc) & VACC_MASK,
+        m.product_id.max_voltage_uv(),
+        m.fsr.vbus_fsr(n),
+        m.fsr.vsense_fsr(n),
+        rail.shunt_uohm,
+        1,
+    );
+    Some(sum_uw / samples as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regs::Address;
+    use crate::sim::SimBus;
+    use crate::{AddrSelect, PAC194X};
+    use std::fs;
+
+    #[test]
+    fn csv_with_energy_and_rotation() {
+        let dir = std::env::temp_dir().join(format!("pac194x-logger-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let mut board = BoardBuf::new();
+        board.push(Rail::new(
+            "5V, main".to_string(),
+            AddrSelect::GND,
+            1,
+            10_000,
+        ));
+
+        let bus = SimBus::new(&[0x10]);
+        let vpower = 0x4000_0000u32;
+        bus.with(0x10, |dev| {
+            // 4.5 V, 5 A and 22.5 W
+            dev.set_n(Address::Vbusn, 1, &[0x80, 0x00]);
+            dev.set_n(Address::Vsensen, 1, &[0x80, 0x00]);
+            dev.set_n(Address::Vpowern, 1, &vpower.to_be_bytes());
+        });
+        let mut sensor = PAC194X::new(bus.clone(), AddrSelect::GND).unwrap();
+        let first = sensor.read_measurements().unwrap();
+        // 1024 more conversions at a quarter of the instantaneous power
+        let sum = 1024 * (vpower as u64 >> 4);
+        bus.with(0x10, |dev| {
+            dev.set_n(Address::Vaccn, 1, &sum.to_be_bytes()[1..]);
+            dev.set(Address::AccCount, &1024u32.to_be_bytes());
+        });
+        let second = sensor.read_measurements().unwrap();
+
+        let path = dir.join("rails.csv");
+        let mut logger = Logger::create(&path, Format::Csv, Rotation::Size(1), board).unwrap();
+        let t0 = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        logger.log(t0, &[first]).unwrap();
+        logger.log(t0 + Duration::from_secs(2), &[second]).unwrap();
+        assert_eq!(logger.energy_uj(0), Some(11_250_000));
+        logger.flush().unwrap();
+
+        let first = fs::read_to_string(dir.join("rails-0000.csv")).unwrap();
+        let second = fs::read_to_string(dir.join("rails-0001.csv")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            first,
+            format!(
+                "{CSV_HEADER}1700000000.000000,\"5V, main\",0x10,1,4.500000,5.000000,22.500000,0.000000\n"
+            )
+        );
+        assert_eq!(
+            second,
+            format!(
+                "{CSV_HEADER}1700000002.000000,\"5V, main\",0x10,1,4.500000,5.000000,22.500000,11.250000\n"
+            )
+        );
+    }
+
+    #[test]
+    fn json_escapes_names() {
+        assert_eq!(json_string("a\"b\\\n"), r#""a\"b\\\u000a""#);
+        assert_eq!(decimal(-1_500), "-0.001500");
+    }
+}
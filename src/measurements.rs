@@ -0,0 +1,292 @@
+//! Reading every result register of the device in a single transaction
+
+use crate::regs::*;
+use crate::{Error, PAC194X, ProductId, convert};
+use embedded_hal::i2c::I2c;
+use packed_struct::prelude::*;
+
+#[cfg(feature = "float")]
+use crate::convert::{vbus_to_real, vsense_to_real};
+
+/// Size of the per-channel result registers, in the order they are laid out in the register map
+const VACC_SIZE: usize = 7;
+const VBUS_SIZE: usize = 2;
+const VSENSE_SIZE: usize = 2;
+const VPOWER_SIZE: usize = 4;
+const ACC_COUNT_SIZE: usize = 4;
+const CHANNEL_SIZE: usize = VACC_SIZE + 4 * VBUS_SIZE + VPOWER_SIZE;
+
+/// The state of the device that determines how the auto-incrementing address pointer walks the
+/// result registers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ChannelState {
+    /// Active channel mask from [`CtrlAct`]
+    pub(crate) off: Channels,
+    /// [`SmbusSettings::no_skip`]
+    pub(crate) no_skip: bool,
+}
+
+impl ChannelState {
+    pub(crate) fn is_off(&self, n: u8) -> bool {
+        match n {
+            1 => self.off._1,
+            2 => self.off._2,
+            3 => self.off._3,
+            4 => self.off._4,
+            _ => panic!("Channel n must be between 1 and 4"),
+        }
+    }
+
+    /// Whether the registers of channel `n` are part of an auto-incrementing read
+    fn is_read(&self, n: u8) -> bool {
+        self.no_skip || !self.is_off(n)
+    }
+}
+
+/// The results of a single channel, as latched by the most recent REFRESH
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChannelMeasurements {
+    pub vacc: Vaccn,
+    pub vbus: Vbusn,
+    pub vsense: Vsensen,
+    pub vbus_avg: VbusnAvg,
+    pub vsense_avg: VsensenAvg,
+    pub vpower: Vpowern,
+}
+
+/// Every result register of the device, read in one block transaction by [`PAC194X::read_measurements`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurements {
+    pub acc_count: AccCount,
+    /// Results of channels 1 through 4, `None` if the channel is disabled
+    pub channels: [Option<ChannelMeasurements>; 4],
+    /// The full scale ranges the results were measured with
+    pub fsr: NegPwrFsrLat,
+    pub product_id: ProductId,
+    /// The 7-bit I2C address of the device
+    pub address: u8,
+}
+
+impl Measurements {
+    /// The results of channel `n`, `None` if the channel is disabled
+    pub fn channel(&self, n: u8) -> Option<&ChannelMeasurements> {
+        assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
+        self.channels[(n - 1) as usize].as_ref()
+    }
+
+    /// The bus voltage of channel `n` in microvolts
+    pub fn bus_voltage_uv(&self, n: u8) -> Option<i32> {
+        let ch = self.channel(n)?;
+        Some(convert::vbus_to_uv(
+            ch.vbus.voltage,
+            self.product_id.max_voltage_uv(),
+            self.fsr.vbus_fsr(n),
+        ))
+    }
+
+    /// The sense voltage of channel `n` in microvolts
+    pub fn sense_voltage_uv(&self, n: u8) -> Option<i32> {
+        let ch = self.channel(n)?;
+        Some(convert::vsense_to_uv(
+            ch.vsense.voltage,
+            self.fsr.vsense_fsr(n),
+        ))
+    }
+
+    /// The current of channel `n` in microamps, given the sense resistor value in micro-ohms
+    pub fn current_ua(&self, n: u8, shunt_uohm: u32) -> Option<i64> {
+        let ch = self.channel(n)?;
+        Some(convert::vsense_to_ua(
+            ch.vsense.voltage,
+            self.fsr.vsense_fsr(n),
+            shunt_uohm,
+        ))
+    }
+
+    /// The power of channel `n` in microwatts, given the sense resistor value in micro-ohms
+    pub fn power_uw(&self, n: u8, shunt_uohm: u32) -> Option<i64> {
+        let ch = self.channel(n)?;
+        Some(convert::vpower_to_uw(
+            ch.vpower.power,
+            self.product_id.max_voltage_uv(),
+            self.fsr.vbus_fsr(n),
+            self.fsr.vsense_fsr(n),
+            shunt_uohm,
+        ))
+    }
+
+    #[cfg(feature = "float")]
+    /// The bus voltage of channel `n`
+    pub fn bus_voltage(&self, n: u8) -> Option<f32> {
+        let ch = self.channel(n)?;
+        Some(vbus_to_real(
+            ch.vbus.voltage,
+            self.product_id.max_voltage(),
+            self.fsr.vbus_fsr(n),
+        ))
+    }
+
+    #[cfg(feature = "float")]
+    /// The sense voltage of channel `n`
+    pub fn sense_voltage(&self, n: u8) -> Option<f32> {
+        let ch = self.channel(n)?;
+        Some(vsense_to_real(ch.vsense.voltage, self.fsr.vsense_fsr(n)))
+    }
+}
+
+impl<E, I> PAC194X<I>
+where
+    I: I2c<Error = E>,
+{
+    /// Returns the active channels and auto-increment settings
+    ///
+    /// [`CtrlAct`] is read every time since a REFRESH the driver didn't issue can change it, while
+    /// [`SmbusSettings::no_skip`] is cached until the driver writes SMBUS_SETTINGS.
+    pub(crate) fn channel_state(&mut self) -> Result<ChannelState, Error<E>> {
+        let off = self.read_ctrl_act()?.channel_n_off;
+        let no_skip = match self.no_skip {
+            Some(no_skip) => no_skip,
+            None => self.read_smub_settings()?.no_skip,
+        };
+        self.no_skip = Some(no_skip);
+        Ok(ChannelState { off, no_skip })
+    }
+
+    /// Reads the accumulator count and every result register of every channel in a single block read.
+    ///
+    /// The read starts at [`AccCount`] and relies on the auto-incrementing address pointer, so the driver
+    /// accounts for the channels disabled in [`CtrlAct`] that the device skips unless
+    /// [`SmbusSettings::no_skip`] is set. Disabled channels are reported as `None`.
+    pub fn read_measurements(&mut self) -> Result<Measurements, Error<E>> {
+        self.check_por()?;
+        let state = self.channel_state()?;
+        let fsr = self.read_neg_pwr_fsr_lat()?;
+        let read: [bool; 4] = core::array::from_fn(|i| state.is_read(i as u8 + 1));
+        let k = read.iter().filter(|r| **r).count();
+
+        let mut buf = [0u8; ACC_COUNT_SIZE + 4 * CHANNEL_SIZE];
+        let len = ACC_COUNT_SIZE + k * CHANNEL_SIZE;
+        if !self.byte_count {
+            self.block_read_into(Address::AccCount as u8, &mut buf[..len])?;
+        } else {
+            // With the byte count, each block read returns a single register
+            self.block_read_into(Address::AccCount as u8, &mut buf[..ACC_COUNT_SIZE])?;
+            let mut start = ACC_COUNT_SIZE;
+            for (addr, size) in [
+                (Address::Vaccn, VACC_SIZE),
+                (Address::Vbusn, VBUS_SIZE),
+                (Address::Vsensen, VSENSE_SIZE),
+                (Address::VbusnAvg, VBUS_SIZE),
+                (Address::VsensenAvg, VSENSE_SIZE),
+                (Address::Vpowern, VPOWER_SIZE),
+            ] {
+                let addr = addr as u8;
+                for n in (0..4u8).filter(|n| read[*n as usize]) {
+                    self.block_read_into(addr + n, &mut buf[start..start + size])?;
+                    start += size;
+                }
+            }
+        }
+
+        // Each result register is laid out as a group of `k` contiguous channels
+        let group = |offset: usize, size: usize, slot: usize| {
+            let start = ACC_COUNT_SIZE + k * offset + slot * size;
+            &buf[start..start + size]
+        };
+        let mut channels = [None; 4];
+        let mut slot = 0;
+        for (i, channel) in channels.iter_mut().enumerate() {
+            if !read[i] {
+                continue;
+            }
+            if !state.is_off(i as u8 + 1) {
+                let mut offset = 0;
+                let mut next = |size| {
+                    let bytes = group(offset, size, slot);
+                    offset += size;
+                    bytes
+                };
+                *channel = Some(ChannelMeasurements {
+                    vacc: Vaccn::unpack_from_slice(next(VACC_SIZE)).unwrap(),
+                    vbus: Vbusn::unpack_from_slice(next(VBUS_SIZE)).unwrap(),
+                    vsense: Vsensen::unpack_from_slice(next(VSENSE_SIZE)).unwrap(),
+                    vbus_avg: VbusnAvg::unpack_from_slice(next(VBUS_SIZE)).unwrap(),
+                    vsense_avg: VsensenAvg::unpack_from_slice(next(VSENSE_SIZE)).unwrap(),
+                    vpower: Vpowern::unpack_from_slice(next(VPOWER_SIZE)).unwrap(),
+                });
+            }
+            slot += 1;
+        }
+
+        Ok(Measurements {
+            acc_count: AccCount::unpack_from_slice(&buf[..ACC_COUNT_SIZE]).unwrap(),
+            channels,
+            fsr,
+            product_id: self.product_id,
+            address: self.address,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AddrSelect;
+    use crate::sim::SimBus;
+
+    const ADDR: u8 = AddrSelect::GND as u8;
+
+    #[test]
+    fn every_channel_off_combination() {
+        for mask in 0..16u8 {
+            for no_skip in [false, true] {
+                let bus = SimBus::new(&[ADDR]);
+                bus.with(ADDR, |dev| {
+                    for n in 1..=4u8 {
+                        let v = n as u16;
+                        dev.set_n(Address::Vaccn, n, &[0, n, n, n, n, n, n]);
+                        dev.set_n(Address::Vbusn, n, &(0x1000 * v).to_be_bytes());
+                        dev.set_n(Address::Vsensen, n, &(0x2000 + v).to_be_bytes());
+                        dev.set_n(Address::VbusnAvg, n, &(0x3000 + v).to_be_bytes());
+                        dev.set_n(Address::VsensenAvg, n, &(0x4000 + v).to_be_bytes());
+                        dev.set_n(Address::Vpowern, n, &(0x400 * v as u32).to_be_bytes());
+                    }
+                    dev.set(Address::AccCount, &[0, 0, 0x12, 0x34]);
+                    dev.set(Address::CtrlAct, &[0, mask << 4]);
+                    dev.set(Address::SmbusSettings, &[(no_skip as u8) << 1]);
+                });
+                let mut sensor = PAC194X::new(bus.clone(), AddrSelect::GND).unwrap();
+                let m = sensor.read_measurements().unwrap();
+                assert_eq!(m.acc_count.count, 0x1234);
+                for n in 1..=4u8 {
+                    let off = mask & (0b1000 >> (n - 1)) != 0;
+                    match m.channel(n) {
+                        None => assert!(off, "mask {mask:04b} no_skip {no_skip} ch{n}"),
+                        Some(ch) => {
+                            assert!(!off, "mask {mask:04b} no_skip {no_skip} ch{n}");
+                            assert_eq!(ch.vacc.sum, 0x0101_0101_0101 * n as u64);
+                            assert_eq!(ch.vbus.voltage, 0x1000 * n as u16);
+                            assert_eq!(ch.vsense.voltage, 0x2000 + n as u16);
+                            assert_eq!(ch.vbus_avg.voltage, 0x3000 + n as u16);
+                            assert_eq!(ch.vsense_avg.voltage, 0x4000 + n as u16);
+                            assert_eq!(ch.vpower.power, 0x100 * n as u32);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn channel_state_follows_ctrl() {
+        let bus = SimBus::new(&[ADDR]);
+        let mut sensor = PAC194X::new(bus.clone(), AddrSelect::GND).unwrap();
+        assert!(sensor.read_measurements().unwrap().channel(2).is_some());
+        sensor
+            .batch(|s| s.modify_ctrl(|ctrl| ctrl.channel_n_off._2 = true))
+            .unwrap();
+        let m = sensor.read_measurements().unwrap();
+        assert!(m.channel(1).is_some());
+        assert!(m.channel(2).is_none());
+    }
+}
@@ -0,0 +1,283 @@
+//! Lifetime energy and charge metering beyond the hardware accumulators
+//!
+//! The 56-bit accumulators and the 32-bit ACC_COUNT eventually overflow, and only a REFRESH, which also
+//! resets them, makes room again. A [`Meter`] harvests them with [`PAC194X::harvest`] and folds what was
+//! accumulated since the previous harvest into 128-bit totals per channel, in nanojoules for the
+//! accumulators set to [`AccumSetting::VPower`] and in nanocoulombs for those set to
+//! [`AccumSetting::VSense`].
+//!
+//! Each harvest latches the accumulators with REFRESH_V, which doesn't reset them, and adds the
+//! difference from the previous harvest. Once ACC_COUNT or an accumulator is latched at least half full
+//! the same harvest follows with a REFRESH: it latches the final values and restarts the accumulators
+//! from zero in the same command, so no sample is lost at the boundary. At full scale a VPOWER
+//! accumulator fills half of its range in about 9 hours at 1024 SPS, so harvest at least that often to
+//! reset it before it overflows.
+//!
+//! A REFRESH from anywhere else also resets the accumulators. A harvest that finds ACC_COUNT below its
+//! previous value starts over from what was latched, and the samples between the previous harvest and
+//! that REFRESH are lost.
+
+use crate::convert::{self, signed_56};
+use crate::fullness::is_bipolar;
+use crate::measurements::Measurements;
+use crate::regs::{AccumConfigLat, AccumSetting};
+use crate::{Error, PAC194X};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+
+/// ACC_COUNT from which a harvest resets the accumulators
+const RESET_COUNT: u32 = 1 << 31;
+
+/// Accumulator magnitude from which a harvest resets the accumulators, half of the capacity
+fn reset_vacc(bipolar: bool) -> i128 {
+    if bipolar { 1 << 54 } else { 1 << 55 }
+}
+
+/// The results of a harvest, as latched by a REFRESH_V or REFRESH
+struct Latched {
+    /// Accumulation rate of the latched results
+    rate: u32,
+    accum: AccumConfigLat,
+    m: Measurements,
+}
+
+impl Latched {
+    /// The signed accumulator of channel `n` and whether it is bipolar, `None` if it is disabled
+    fn vacc(&self, n: u8) -> Option<(i128, bool)> {
+        let ch = self.m.channel(n)?;
+        let (vbus_fsr, vsense_fsr) = (self.m.fsr.vbus_fsr(n), self.m.fsr.vsense_fsr(n));
+        let bipolar = is_bipolar(self.accum.accum(n), vbus_fsr, vsense_fsr);
+        Some((signed_56(ch.vacc.sum, bipolar), bipolar))
+    }
+
+    /// Whether ACC_COUNT or an accumulator is at least half full
+    fn needs_reset(&self) -> bool {
+        self.m.acc_count.count >= RESET_COUNT
+            || (1..=4u8).any(|n| {
+                self.vacc(n)
+                    .is_some_and(|(vacc, bipolar)| vacc.abs() >= reset_vacc(bipolar))
+            })
+    }
+}
+
+/// The lifetime totals of a channel
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChannelTotals {
+    /// Energy in nanojoules, accumulated while the accumulator was set to [`AccumSetting::VPower`]
+    pub energy_nj: i128,
+    /// Charge in nanocoulombs, accumulated while the accumulator was set to [`AccumSetting::VSense`]
+    pub charge_nc: i128,
+}
+
+/// Software totals of the accumulators of a device, extended to 128 bits
+#[derive(Debug, Clone, PartialEq)]
+pub struct Meter {
+    /// Sense resistor of each channel in micro-ohms, 0 for channels that aren't metered
+    shunts_uohm: [u32; 4],
+    totals: [ChannelTotals; 4],
+    samples: u64,
+    /// ACC_COUNT and the signed accumulators at the previous harvest, 0 after a reset
+    last_count: u32,
+    last_vacc: [i128; 4],
+    harvests: u32,
+}
+
+impl Meter {
+    /// A meter with nothing accumulated, for channels with sense resistors of `shunts_uohm` micro-ohms
+    /// (0 for channels that aren't metered)
+    ///
+    /// The first harvest takes whatever the accumulators hold since their last reset.
+    pub fn new(shunts_uohm: [u32; 4]) -> Self {
+        Self {
+            shunts_uohm,
+            totals: [ChannelTotals::default(); 4],
+            samples: 0,
+            last_count: 0,
+            last_vacc: [0; 4],
+            harvests: 0,
+        }
+    }
+
+    /// The lifetime totals of channel `n` (1 to 4)
+    pub fn totals(&self, n: u8) -> ChannelTotals {
+        assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
+        self.totals[(n - 1) as usize]
+    }
+
+    /// The lifetime energy of channel `n` (1 to 4) in microjoules
+    pub fn energy_uj(&self, n: u8) -> i128 {
+        self.totals(n).energy_nj / 1000
+    }
+
+    /// The lifetime charge of channel `n` (1 to 4) in microcoulombs
+    pub fn charge_uc(&self, n: u8) -> i128 {
+        self.totals(n).charge_nc / 1000
+    }
+
+    /// The number of samples accumulated over the lifetime of the meter
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// The number of harvests so far
+    pub fn harvests(&self) -> u32 {
+        self.harvests
+    }
+}
+
+impl<E, I> PAC194X<I>
+where
+    I: I2c<Error = E>,
+{
+    /// Latches the accumulators and adds what they accumulated since the previous harvest to `meter`
+    ///
+    /// Issues REFRESH_V and waits 1 ms on `delay` for the results, then REFRESH and another 1 ms if the
+    /// accumulators are at least half full. Disabled channels are skipped. Returns
+    /// [`Error::NoSampleRate`] if the latched results were accumulated in a sample mode without a fixed
+    /// rate and leaves `meter` unchanged, so the next harvest takes those samples at its rate.
+    pub fn harvest<D: DelayNs>(
+        &mut self,
+        meter: &mut Meter,
+        delay: &mut D,
+    ) -> Result<(), Error<E>> {
+        self.refresh_v()?;
+        delay.delay_ms(1);
+        let mut latched = self.read_latched()?;
+        let reset = latched.needs_reset();
+        if reset {
+            self.refresh()?;
+            delay.delay_ms(1);
+            latched = self.read_latched()?;
+        }
+
+        let count = latched.m.acc_count.count;
+        // A REFRESH from elsewhere restarted the accumulators
+        if count < meter.last_count {
+            meter.last_count = 0;
+            meter.last_vacc = [0; 4];
+        }
+        meter.harvests += 1;
+        meter.samples += (count - meter.last_count) as u64;
+        meter.last_count = if reset { 0 } else { count };
+        let max_uv = self.product_id.max_voltage_uv();
+        for n in 1..=4u8 {
+            let i = (n - 1) as usize;
+            let Some((vacc, _)) = latched.vacc(n) else {
+                continue;
+            };
+            let delta = vacc - meter.last_vacc[i];
+            meter.last_vacc[i] = if reset { 0 } else { vacc };
+
+            let shunt = meter.shunts_uohm[i];
+            let (vbus_fsr, vsense_fsr) = (latched.m.fsr.vbus_fsr(n), latched.m.fsr.vsense_fsr(n));
+            let rate = latched.rate;
+            let totals = &mut meter.totals[i];
+            match latched.accum.accum(n) {
+                AccumSetting::VPower if shunt > 0 => {
+                    totals.energy_nj +=
+                        convert::power_sum_to_nj(delta, max_uv, vbus_fsr, vsense_fsr, shunt, rate)
+                }
+                AccumSetting::VSense if shunt > 0 => {
+                    totals.charge_nc += convert::vsense_sum_to_nc(delta, vsense_fsr, shunt, rate)
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the latched results with the settings they were accumulated with
+    fn read_latched(&mut self) -> Result<Latched, Error<E>> {
+        Ok(Latched {
+            rate: self.read_timing_lat()?.acc_rate()?,
+            accum: self.read_accum_config_lat()?,
+            m: self.read_measurements()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AddrSelect;
+    use crate::regs::{Address, SampleMode};
+    use crate::sim::SimBus;
+    use embedded_hal_mock::eh1::delay::NoopDelay;
+
+    /// Sets ACC_COUNT to `count` samples and the accumulator of channel 1 to 45 W for each of them
+    fn accumulate(sensor: &mut PAC194X<SimBus>, count: u32) {
+        sensor.i2c.with(0x10, |dev| {
+            dev.set(Address::AccCount, &count.to_be_bytes());
+            dev.set_n(
+                Address::Vaccn,
+                1,
+                &((count as u64) << 29).to_be_bytes()[1..],
+            );
+            // A quarter of +100 mV on channel 2
+            dev.set_n(
+                Address::Vaccn,
+                2,
+                &((count as u64) << 14).to_be_bytes()[1..],
+            );
+        });
+    }
+
+    #[test]
+    fn harvest_across_resets() {
+        let bus = SimBus::new(&[0x10]);
+        let mut sensor = PAC194X::new(bus, AddrSelect::GND).unwrap();
+        sensor
+            .batch(|s| s.modify_accum_config(|a| a.set_accum(2, AccumSetting::VSense)))
+            .unwrap();
+        let mut meter = Meter::new([10_000, 10_000, 0, 0]);
+        let joules = |meter: &Meter| meter.energy_uj(1) / 1_000_000;
+
+        // One second at 1024 SPS
+        accumulate(&mut sensor, 1024);
+        sensor.harvest(&mut meter, &mut NoopDelay).unwrap();
+        assert_eq!(meter.totals(1).energy_nj, 45_000_000_000);
+        assert_eq!(meter.charge_uc(2), 2_500_000);
+        accumulate(&mut sensor, 2048);
+        sensor.harvest(&mut meter, &mut NoopDelay).unwrap();
+        assert_eq!(joules(&meter), 90);
+        assert_eq!(meter.samples(), 2048);
+
+        // Restarted by a REFRESH from elsewhere
+        accumulate(&mut sensor, 512);
+        sensor.harvest(&mut meter, &mut NoopDelay).unwrap();
+        assert_eq!(meter.energy_uj(1), 112_500_000);
+
+        // Half full: this harvest resets the accumulators and the next one takes all they hold
+        accumulate(&mut sensor, RESET_COUNT);
+        sensor.harvest(&mut meter, &mut NoopDelay).unwrap();
+        let before = joules(&meter);
+        assert_eq!(meter.last_count, 0);
+        accumulate(&mut sensor, 1024);
+        sensor.harvest(&mut meter, &mut NoopDelay).unwrap();
+        assert_eq!(joules(&meter), before + 45);
+        assert_eq!(meter.samples(), 2048 + RESET_COUNT as u64 + 1024);
+        assert_eq!(meter.totals(3), ChannelTotals::default());
+        assert_eq!(meter.harvests(), 5);
+
+        // A unipolar accumulator is half full at 2^55
+        accumulate(&mut sensor, 1 << 25);
+        sensor.harvest(&mut meter, &mut NoopDelay).unwrap();
+        assert_eq!(meter.last_count, 1 << 25);
+        accumulate(&mut sensor, 1 << 26);
+        sensor.harvest(&mut meter, &mut NoopDelay).unwrap();
+        assert_eq!(meter.last_count, 0);
+
+        // Results without a fixed rate leave the meter alone
+        let before = meter.clone();
+        sensor
+            .batch(|s| s.modify_ctrl(|ctrl| ctrl.sample_mode = SampleMode::Sleep))
+            .unwrap();
+        accumulate(&mut sensor, 1024);
+        sensor.refresh().unwrap();
+        assert_eq!(
+            sensor.harvest(&mut meter, &mut NoopDelay),
+            Err(Error::NoSampleRate(SampleMode::Sleep))
+        );
+        assert_eq!(meter, before);
+    }
+}
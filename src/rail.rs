@@ -0,0 +1,322 @@
+//! Describing a board by its rails
+//!
+//! A [`Rail`] names a channel of a device (by its [`AddrSelect`] and channel number) and describes its
+//! sense resistor, expected bus voltage and full scale ranges. A [`Board`] is a table of rails that
+//! turns [`Measurements`] into [`RailReading`]s labelled with the rail name.
+//!
+//! Rail names are generic, so a board can be a `static` table of `Rail<&'static str>` on `no_std`
+//! targets or be built at runtime from `Rail<String>` in a [`BoardBuf`] with the `std` feature.
+
+use crate::measurements::Measurements;
+use crate::regs::{VBusFSR, VSenseFSR};
+use crate::{AddrSelect, Error, PAC194X};
+use embedded_hal::i2c::I2c;
+
+/// A named channel of a device
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rail<S = &'static str> {
+    pub name: S,
+    pub addr_sel: AddrSelect,
+    /// Channel of the device, 1 to 4
+    pub channel: u8,
+    /// Sense resistor value in micro-ohms
+    pub shunt_uohm: u32,
+    /// Lowest expected bus voltage in microvolts
+    pub min_uv: i32,
+    /// Highest expected bus voltage in microvolts
+    pub max_uv: i32,
+    pub vbus_fsr: VBusFSR,
+    pub vsense_fsr: VSenseFSR,
+}
+
+impl<S> Rail<S> {
+    /// A rail with unipolar ranges and any positive bus voltage expected
+    pub const fn new(name: S, addr_sel: AddrSelect, channel: u8, shunt_uohm: u32) -> Self {
+        Self {
+            name,
+            addr_sel,
+            channel,
+            shunt_uohm,
+            min_uv: 0,
+            max_uv: i32::MAX,
+            vbus_fsr: VBusFSR::Unipolar,
+            vsense_fsr: VSenseFSR::Unipolar,
+        }
+    }
+
+    /// Sets the expected bus voltage range in microvolts
+    pub const fn with_range(mut self, min_uv: i32, max_uv: i32) -> Self {
+        self.min_uv = min_uv;
+        self.max_uv = max_uv;
+        self
+    }
+
+    /// Sets the full scale ranges
+    pub const fn with_fsr(mut self, vbus_fsr: VBusFSR, vsense_fsr: VSenseFSR) -> Self {
+        self.vbus_fsr = vbus_fsr;
+        self.vsense_fsr = vsense_fsr;
+        self
+    }
+}
+
+/// The results of a rail, converted with its sense resistor value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RailReading<'a, S = &'static str> {
+    pub rail: &'a Rail<S>,
+    pub bus_voltage_uv: i32,
+    pub sense_voltage_uv: i32,
+    pub current_ua: i64,
+    pub power_uw: i64,
+}
+
+impl<S: AsRef<str>> RailReading<'_, S> {
+    /// The name of the rail
+    pub fn name(&self) -> &str {
+        self.rail.name.as_ref()
+    }
+}
+
+impl<S> RailReading<'_, S> {
+    /// Whether the bus voltage is within the expected range of the rail
+    pub fn in_range(&self) -> bool {
+        (self.rail.min_uv..=self.rail.max_uv).contains(&self.bus_voltage_uv)
+    }
+}
+
+/// Problems with a board description, holding the index of the offending rail
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BoardError {
+    /// The name is used by an earlier rail
+    DuplicateName(usize),
+    /// The channel is used by an earlier rail
+    DuplicateChannel(usize),
+    /// The channel isn't between 1 and 4
+    InvalidChannel(usize),
+    /// The sense resistor value is zero
+    InvalidShunt(usize),
+    /// The lowest expected voltage is above the highest
+    InvalidRange(usize),
+}
+
+/// A table of rails
+#[derive(Debug, Clone, Copy)]
+pub struct Board<'a, S = &'static str> {
+    rails: &'a [Rail<S>],
+}
+
+impl<'a, S: AsRef<str>> Board<'a, S> {
+    pub const fn new(rails: &'a [Rail<S>]) -> Self {
+        Self { rails }
+    }
+
+    /// Every rail of the board
+    pub fn rails(&self) -> &'a [Rail<S>] {
+        self.rails
+    }
+
+    /// The rail called `name`
+    pub fn rail(&self, name: &str) -> Option<&'a Rail<S>> {
+        self.rails.iter().find(|r| r.name.as_ref() == name)
+    }
+
+    /// Checks the rails for duplicate names and channels and invalid values
+    pub fn check(&self) -> Result<(), BoardError> {
+        for (i, rail) in self.rails.iter().enumerate() {
+            let earlier = &self.rails[..i];
+            if earlier
+                .iter()
+                .any(|r| r.name.as_ref() == rail.name.as_ref())
+            {
+                return Err(BoardError::DuplicateName(i));
+            }
+            if earlier
+                .iter()
+                .any(|r| r.addr_sel == rail.addr_sel && r.channel == rail.channel)
+            {
+                return Err(BoardError::DuplicateChannel(i));
+            }
+            if !(1..=4).contains(&rail.channel) {
+                return Err(BoardError::InvalidChannel(i));
+            }
+            if rail.shunt_uohm == 0 {
+                return Err(BoardError::InvalidShunt(i));
+            }
+            if rail.min_uv > rail.max_uv {
+                return Err(BoardError::InvalidRange(i));
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the full scale ranges of the rails of `sensor` to [`NegPwrFsr`](crate::regs::NegPwrFsr).
+    ///
+    /// Like any change to NEG_PWR_FSR, the ranges take effect on the next REFRESH. Nothing is written if
+    /// [`check`](Self::check) fails, returning [`Error::InvalidBoard`], or if a rail of `sensor` is on a
+    /// channel the part doesn't have, returning [`Error::NoSuchChannel`].
+    pub fn configure<E, I>(&self, sensor: &mut PAC194X<I>) -> Result<(), Error<E>>
+    where
+        I: I2c<Error = E>,
+    {
+        self.check().map_err(Error::InvalidBoard)?;
+        let address = sensor.address();
+        let channels = sensor.product_id.channels();
+        let mut rails = self.rails.iter().filter(|r| r.addr_sel as u8 == address);
+        if let Some(rail) = rails.clone().find(|r| r.channel > channels) {
+            return Err(Error::NoSuchChannel(rail.channel));
+        }
+        sensor.modify_neg_pwr_fsr(|fsr| {
+            for rail in rails.by_ref() {
+                fsr.set_vbus_fsr(rail.channel, rail.vbus_fsr);
+                fsr.set_vsense_fsr(rail.channel, rail.vsense_fsr);
+            }
+        })
+    }
+
+    /// The reading of the rail called `name` from the measurements of its device, `None` if there is no
+    /// such rail, no measurements of its device or its channel is disabled
+    pub fn reading(&self, name: &str, measurements: &[Measurements]) -> Option<RailReading<'a, S>> {
+        read(self.rail(name)?, measurements)
+    }
+
+    /// The readings of every rail with measurements, in the order of the table
+    pub fn readings<'m>(
+        &self,
+        measurements: &'m [Measurements],
+    ) -> impl Iterator<Item = RailReading<'a, S>> + 'm
+    where
+        'a: 'm,
+    {
+        self.rails
+            .iter()
+            .filter_map(|rail| read(rail, measurements))
+    }
+}
+
+fn read<'a, S>(rail: &'a Rail<S>, measurements: &[Measurements]) -> Option<RailReading<'a, S>> {
+    let m = measurements
+        .iter()
+        .find(|m| m.address == rail.addr_sel as u8)?;
+    let n = rail.channel;
+    Some(RailReading {
+        rail,
+        bus_voltage_uv: m.bus_voltage_uv(n)?,
+        sense_voltage_uv: m.sense_voltage_uv(n)?,
+        current_ua: m.current_ua(n, rail.shunt_uohm)?,
+        power_uw: m.power_uw(n, rail.shunt_uohm)?,
+    })
+}
+
+/// A board description that owns its rails and their names
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BoardBuf {
+    pub rails: Vec<Rail<String>>,
+}
+
+#[cfg(feature = "std")]
+impl BoardBuf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rail to the end of the table
+    pub fn push(&mut self, rail: Rail<String>) {
+        self.rails.push(rail);
+    }
+
+    /// Borrows the rails as a [`Board`]
+    pub fn as_board(&self) -> Board<'_, String> {
+        Board::new(&self.rails)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regs::Address;
+    use crate::sim::SimBus;
+
+    static RAILS: [Rail; 3] = [
+        Rail::new("5V_MAIN", AddrSelect::GND, 1, 10_000).with_range(4_750_000, 5_250_000),
+        Rail::new("VCORE", AddrSelect::GND, 2, 2_000),
+        Rail::new("3V3", AddrSelect::_499, 1, 10_000)
+            .with_fsr(VBusFSR::Unipolar, VSenseFSR::BipolarHV),
+    ];
+
+    #[test]
+    fn read_rails_by_name() {
+        let board = Board::new(&RAILS);
+        assert_eq!(board.check(), Ok(()));
+        let bus = SimBus::new(&[0x10, 0x11]);
+        bus.with(0x10, |dev| {
+            // 4.5 V and 50 mV
+            dev.set_n(Address::Vbusn, 1, &[0x80, 0x00]);
+            dev.set_n(Address::Vsensen, 1, &[0x80, 0x00]);
+        });
+        let mut gnd = PAC194X::new(bus.clone(), AddrSelect::GND).unwrap();
+        let mut second = PAC194X::new(bus.clone(), AddrSelect::_499).unwrap();
+        board.configure(&mut second).unwrap();
+        second.refresh().unwrap();
+        // The latched ranges follow the active ones one REFRESH later
+        second.refresh().unwrap();
+        let m = [gnd.read_measurements().unwrap()];
+
+        let main = board.reading("5V_MAIN", &m).unwrap();
+        assert_eq!(main.name(), "5V_MAIN");
+        assert_eq!(main.bus_voltage_uv, 4_500_000);
+        assert_eq!(main.current_ua, 5_000_000);
+        assert!(!main.in_range());
+        assert!(board.reading("3V3", &m).is_none());
+        assert!(board.reading("12V", &m).is_none());
+
+        let m = [m[0], second.read_measurements().unwrap()];
+        let names: std::vec::Vec<_> = board.readings(&m).map(|r| r.rail.name).collect();
+        assert_eq!(names, ["5V_MAIN", "VCORE", "3V3"]);
+        assert_eq!(m[1].fsr.vsense_fsr(1), VSenseFSR::BipolarHV);
+    }
+
+    #[test]
+    fn check_finds_problems() {
+        let rails = [
+            Rail::new("A", AddrSelect::GND, 1, 1),
+            Rail::new("B", AddrSelect::GND, 1, 1),
+        ];
+        assert_eq!(
+            Board::new(&rails).check(),
+            Err(BoardError::DuplicateChannel(1))
+        );
+        let rails = [Rail::new("A", AddrSelect::GND, 5, 1)];
+        assert_eq!(
+            Board::new(&rails).check(),
+            Err(BoardError::InvalidChannel(0))
+        );
+        let bus = SimBus::new(&[0x10]);
+        let mut sensor =
+            PAC194X::new_with_product_id(bus, AddrSelect::GND, crate::ProductId::PAC1942_1);
+        assert_eq!(
+            Board::new(&rails).configure(&mut sensor),
+            Err(Error::InvalidBoard(BoardError::InvalidChannel(0)))
+        );
+        let rails = [Rail::new("A", AddrSelect::GND, 3, 1)];
+        assert_eq!(
+            Board::new(&rails).configure(&mut sensor),
+            Err(Error::NoSuchChannel(3))
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn owned_names() {
+        let mut board = BoardBuf::new();
+        for (i, name) in ["VDDQ", "VPP"].into_iter().enumerate() {
+            board.push(Rail::new(
+                name.to_string(),
+                AddrSelect::VDD,
+                i as u8 + 1,
+                5_000,
+            ));
+        }
+        assert_eq!(board.as_board().check(), Ok(()));
+        assert_eq!(board.as_board().rail("VPP").unwrap().channel, 2);
+    }
+}
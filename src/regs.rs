@@ -89,7 +89,7 @@ pub enum GpioAlert {
     Slow,
 }
 
-#[derive(PackedStruct, Default, Debug, PartialEq)]
+#[derive(PackedStruct, Clone, Copy, Default, Debug, PartialEq)]
 #[packed_struct(bit_numbering = "msb0")]
 /// Channels to be potentially disabled
 pub struct Channels {
@@ -100,7 +100,31 @@ pub struct Channels {
     pub _4: bool,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+impl Channels {
+    /// Whether channel `n` (1 to 4) is set
+    pub fn get(&self, n: u8) -> bool {
+        match n {
+            1 => self._1,
+            2 => self._2,
+            3 => self._3,
+            4 => self._4,
+            _ => panic!("Channel n must be between 1 and 4"),
+        }
+    }
+
+    /// Sets channel `n` (1 to 4)
+    pub fn set(&mut self, n: u8, value: bool) {
+        match n {
+            1 => self._1 = value,
+            2 => self._2 = value,
+            3 => self._3 = value,
+            4 => self._4 = value,
+            _ => panic!("Channel n must be between 1 and 4"),
+        }
+    }
+}
+
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// Primary control registeer
 pub struct Ctrl {
@@ -123,7 +147,7 @@ pub struct Ctrl {
     pub channel_n_off: Channels,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "4", bit_numbering = "lsb0")]
 /// This register contains the count for each time a power result is summed in the
 /// accumulator.
@@ -132,7 +156,7 @@ pub struct AccCount {
     pub count: u32,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "7", bit_numbering = "lsb0")]
 /// This register contains the accumulated sum of V POWER samples, where n = 1 to 4,
 /// depending on the device by default. It can also hold the accumulated values of V SENSE and VBUS if
@@ -150,7 +174,7 @@ pub struct Vaccn {
     pub sum: u64,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 ///  This register contains the most recent digitized value of a VBUS sample, where n = 1 to
 /// 4, depending on the device. These are 16-bit unsigned numbers, unless VBUS is configured to have a
@@ -160,7 +184,7 @@ pub struct Vbusn {
     pub voltage: u16,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// This register contains the most recent digitized value of V SENSE samples, where n
 /// = 1 to 4, depending on the device. These are 16-bit unsigned numbers, unless V SENSE is configured
@@ -170,7 +194,7 @@ pub struct Vsensen {
     pub voltage: u16,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// This register contain a rolling average of the eight most recent V BUS
 /// measurements. It has the same format as the values in the VBUS registers.
@@ -179,7 +203,7 @@ pub struct VbusnAvg {
     pub voltage: u16,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// This register contain a rolling average of the eight most recent V SENSE
 /// measurements. It has the same format as the values in the V SENSE registers.
@@ -188,7 +212,7 @@ pub struct VsensenAvg {
     pub voltage: u16,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "4", bit_numbering = "lsb0")]
 /// This register contains the product of V BUS (14 MSBs) and V SENSE, which
 /// represents the proportional power for each channel. These are 30-bit unsigned numbers unless either
@@ -206,7 +230,7 @@ pub struct Vpowern {
     pub power: u32,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "1", bit_numbering = "lsb0")]
 /// Bits in this register may be written or read at any time and are active immediately. Refresh is not required to activate
 /// them or update them.
@@ -292,7 +316,7 @@ pub enum VBusFSR {
     BipolarLV = 2,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// Configures the nth channel FSR for Vsense and Vbus measurement
 pub struct NegPwrFsr {
@@ -314,7 +338,7 @@ pub struct NegPwrFsr {
     pub cfg_vb4: VBusFSR,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "1", bit_numbering = "lsb0")]
 /// This register tracks the state of the SLOW pin, tracks transitions on the SLOW pin and controls the type of limited
 /// REFRESH command (if any) that will occur on a SLOW pin transition. This allows software to monitor the state of the
@@ -370,7 +394,7 @@ pub struct Slow {
     pub r_v_fall: bool,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// This register contains an image of [`Ctrl`] and reflects the current active value of these settings, whereas the
 /// values in register 01h may be programmed but not activated by one of the REFRESH commands. This register
@@ -399,7 +423,7 @@ pub struct CtrlAct {
     pub channel_n_off: Channels,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// This register contains an image of [`NegPwrFsr`]. The bits in this register reflect the current active value of these set-
 /// tings, whereas the values in register 1Dh may be programmed but not activated by one of the REFRESH commands.
@@ -424,7 +448,7 @@ pub struct NegPwrFsrAct {
     pub cfg_vb4: VBusFSR,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// This register contains an image of [`Ctrl`]. The bits in this register reflect the value of these settings, that was
 /// active before the most recent REFRESH command (including REFRESH_V and/or REFRESH_G). The values in
@@ -453,7 +477,7 @@ pub struct CtrlLat {
     pub channel_n_off: Channels,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// This register contains an image of [`NegPwrFsr`].
 /// The bits in this register reflect the settings that were active before the
@@ -480,6 +504,63 @@ pub struct NegPwrFsrLat {
     pub cfg_vb4: VBusFSR,
 }
 
+/// Per-channel getters for the registers with one VBUS and VSENSE full scale range per channel
+macro_rules! fsr_getters {
+    ($($type:ty),*) => {
+        $(
+            impl $type {
+                /// The VBUS full scale range of channel `n` (1 to 4)
+                pub fn vbus_fsr(&self, n: u8) -> VBusFSR {
+                    match n {
+                        1 => self.cfg_vb1,
+                        2 => self.cfg_vb2,
+                        3 => self.cfg_vb3,
+                        4 => self.cfg_vb4,
+                        _ => panic!("Channel n must be between 1 and 4"),
+                    }
+                }
+
+                /// The VSENSE full scale range of channel `n` (1 to 4)
+                pub fn vsense_fsr(&self, n: u8) -> VSenseFSR {
+                    match n {
+                        1 => self.cfg_vs1,
+                        2 => self.cfg_vs2,
+                        3 => self.cfg_vs3,
+                        4 => self.cfg_vs4,
+                        _ => panic!("Channel n must be between 1 and 4"),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+fsr_getters!(NegPwrFsrAct, NegPwrFsrLat);
+
+impl NegPwrFsr {
+    /// Sets the VBUS full scale range of channel `n` (1 to 4)
+    pub fn set_vbus_fsr(&mut self, n: u8, fsr: VBusFSR) {
+        match n {
+            1 => self.cfg_vb1 = fsr,
+            2 => self.cfg_vb2 = fsr,
+            3 => self.cfg_vb3 = fsr,
+            4 => self.cfg_vb4 = fsr,
+            _ => panic!("Channel n must be between 1 and 4"),
+        }
+    }
+
+    /// Sets the VSENSE full scale range of channel `n` (1 to 4)
+    pub fn set_vsense_fsr(&mut self, n: u8, fsr: VSenseFSR) {
+        match n {
+            1 => self.cfg_vs1 = fsr,
+            2 => self.cfg_vs2 = fsr,
+            3 => self.cfg_vs3 = fsr,
+            4 => self.cfg_vs4 = fsr,
+            _ => panic!("Channel n must be between 1 and 4"),
+        }
+    }
+}
+
 #[derive(PrimitiveEnum_u8, Clone, Copy, Debug, PartialEq)]
 /// The voltage an accumulator accumulates
 pub enum AccumSetting {
@@ -488,7 +569,7 @@ pub enum AccumSetting {
     VBus = 2,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "1", bit_numbering = "lsb0")]
 /// This register is used to configure the Accumulator for one of the alternate modes: V SENSE Accumulation (Coulomb
 /// Counting) or V BUS Accumulation (VBUS integration). All bits default to zero, which is the V POWER Accumulation mode
@@ -504,7 +585,87 @@ pub struct AccumConfig {
     pub acc4_config: AccumSetting,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+impl AccumConfig {
+    /// Sets what the accumulator of channel `n` (1 to 4) accumulates
+    pub fn set_accum(&mut self, n: u8, setting: AccumSetting) {
+        match n {
+            1 => self.acc1_config = setting,
+            2 => self.acc2_config = setting,
+            3 => self.acc3_config = setting,
+            4 => self.acc4_config = setting,
+            _ => panic!("Channel n must be between 1 and 4"),
+        }
+    }
+}
+
+/// The per-channel limit ALERTs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitAlert {
+    /// Overcurrent
+    Oc,
+    /// Undercurrent
+    Uc,
+    /// Overvoltage
+    Ov,
+    /// Undervoltage
+    Uv,
+    /// Overpower
+    Op,
+}
+
+impl LimitAlert {
+    pub const ALL: [LimitAlert; 5] = [Self::Oc, Self::Uc, Self::Ov, Self::Uv, Self::Op];
+}
+
+/// Per-channel accessors for the registers with one flag per limit ALERT and channel
+macro_rules! limit_alert_flags {
+    ($($type:ty),*) => {
+        $(
+            impl $type {
+                fn flag_mut(&mut self, alert: LimitAlert, n: u8) -> &mut bool {
+                    match (alert, n) {
+                        (LimitAlert::Oc, 1) => &mut self.ch1_oc,
+                        (LimitAlert::Oc, 2) => &mut self.ch2_oc,
+                        (LimitAlert::Oc, 3) => &mut self.ch3_oc,
+                        (LimitAlert::Oc, 4) => &mut self.ch4_oc,
+                        (LimitAlert::Uc, 1) => &mut self.ch1_uc,
+                        (LimitAlert::Uc, 2) => &mut self.ch2_uc,
+                        (LimitAlert::Uc, 3) => &mut self.ch3_uc,
+                        (LimitAlert::Uc, 4) => &mut self.ch4_uc,
+                        (LimitAlert::Ov, 1) => &mut self.ch1_ov,
+                        (LimitAlert::Ov, 2) => &mut self.ch2_ov,
+                        (LimitAlert::Ov, 3) => &mut self.ch3_ov,
+                        (LimitAlert::Ov, 4) => &mut self.ch4_ov,
+                        (LimitAlert::Uv, 1) => &mut self.ch1_uv,
+                        (LimitAlert::Uv, 2) => &mut self.ch2_uv,
+                        (LimitAlert::Uv, 3) => &mut self.ch3_uv,
+                        (LimitAlert::Uv, 4) => &mut self.ch4_uv,
+                        (LimitAlert::Op, 1) => &mut self.ch1_op,
+                        (LimitAlert::Op, 2) => &mut self.ch2_op,
+                        (LimitAlert::Op, 3) => &mut self.ch3_op,
+                        (LimitAlert::Op, 4) => &mut self.ch4_op,
+                        _ => panic!("Channel n must be between 1 and 4"),
+                    }
+                }
+
+                /// The flag of `alert` for channel `n` (1 to 4)
+                pub fn limit(&self, alert: LimitAlert, n: u8) -> bool {
+                    let mut flags = *self;
+                    *flags.flag_mut(alert, n)
+                }
+
+                /// Sets the flag of `alert` for channel `n` (1 to 4)
+                pub fn set_limit(&mut self, alert: LimitAlert, n: u8, value: bool) {
+                    *self.flag_mut(alert, n) = value;
+                }
+            }
+        )*
+    };
+}
+
+limit_alert_flags!(AlertStatus, SlowAlert1, GpioAlert2, AlertEnable);
+
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "3", bit_numbering = "msb0")]
 /// Read this register to determine the cause of ALERT being tripped.
 /// This register is cleared when read and another conversion cycle completes. If the
@@ -546,7 +707,7 @@ pub struct AlertStatus {
     pub acc_count: bool,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "3", bit_numbering = "msb0")]
 /// Write to this register to assign a specific ALERT signal to the SLOW/ALERT1 pin. The SLOW/ALERT1 pin must be
 /// configured for the ALERT function in [`Ctrl`] for this register to control the pin.
@@ -595,7 +756,7 @@ pub struct SlowAlert1 {
     pub alert_cc1: bool,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "3", bit_numbering = "msb0")]
 /// Write to this register to assign a specific ALERT signal to the GPIO/ALERT2 pin. The GPIO/ALERT2 pin must be
 /// configured for the ALERT function in [`Ctrl`] for this register to control the pin.
@@ -655,7 +816,7 @@ pub enum AccFullness {
     Partially = 3,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// These limits are used to set a limit for how full the Accumulators and Accumulator Count registers can be before the
 /// Accumulator Full and Accumulator Count full limits are tripped. This allows an ALERT to be registered when the
@@ -674,7 +835,7 @@ pub struct AccFullnessLimits {
     pub acc_count_full: AccFullness,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// Overcurrent (OC) limit for each channel. This limit is a two’s complement number for
 /// all modes. Disable ALERTs in [`AlertEnable`] before changing the value to avoid false triggers. Each
@@ -684,7 +845,7 @@ pub struct OcLimitn {
     pub limit: i16,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// Undercurrent (UC) limit for each channel. This limit is a two’s complement number for
 /// all modes. Disable ALERTs in [`AlertEnable`] before changing the value to avoid false triggers. Each
@@ -694,7 +855,7 @@ pub struct UcLimitn {
     pub limit: i16,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "3", bit_numbering = "lsb0")]
 /// Overpower (OP) limit for each channel. This limit is a two’s complement number for
 /// all modes. These 24 bits correspond to the upper 24 MSBs in the VPOWER number. The OP limit (only)
@@ -706,7 +867,7 @@ pub struct OpLimitn {
     pub limit: i32,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// Overvoltage (OV) limit for each channel. This limit is a two’s complement number for
 /// all modes. Disable ALERTs in [`AlertEnable`] before changing the value to avoid false triggers. Each
@@ -716,7 +877,7 @@ pub struct OvLimitn {
     pub limit: i16,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "2", bit_numbering = "lsb0")]
 /// Undervoltage (UV) limit for each channel. This limit is a two’s complement number for
 /// all modes. Disable ALERTs in [`AlertEnable`] before changing the value to avoid false triggers. Each
@@ -736,7 +897,44 @@ pub enum SampleCount {
     _16 = 3,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+impl SampleCount {
+    /// The sample count of 1, 4, 8 or 16 `samples`
+    pub fn from_samples(samples: u8) -> Option<Self> {
+        match samples {
+            1 => Some(Self::_1),
+            4 => Some(Self::_4),
+            8 => Some(Self::_8),
+            16 => Some(Self::_16),
+            _ => None,
+        }
+    }
+}
+
+impl AccFullnessLimits {
+    /// The fullness limit of the accumulator of channel `n` (1 to 4)
+    pub fn acc_full(&self, n: u8) -> AccFullness {
+        match n {
+            1 => self.ch1_acc_full,
+            2 => self.ch2_acc_full,
+            3 => self.ch3_acc_full,
+            4 => self.ch4_acc_full,
+            _ => panic!("Channel n must be between 1 and 4"),
+        }
+    }
+
+    /// Sets the fullness limit of the accumulator of channel `n` (1 to 4)
+    pub fn set_acc_full(&mut self, n: u8, limit: AccFullness) {
+        match n {
+            1 => self.ch1_acc_full = limit,
+            2 => self.ch2_acc_full = limit,
+            3 => self.ch3_acc_full = limit,
+            4 => self.ch4_acc_full = limit,
+            _ => panic!("Channel n must be between 1 and 4"),
+        }
+    }
+}
+
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "1", bit_numbering = "lsb0")]
 /// Number of consecutive samples exceeding the overcurrent limit that are required to trigger the ALERT function for
 /// each channel. The default is 1 sample [`SampleCount::_1`]. The sample counter is not reset until a conversion is completed to con-
@@ -753,7 +951,7 @@ pub struct OcLimitNSamples {
     pub n_samples_ch4: SampleCount,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "1", bit_numbering = "lsb0")]
 /// Number of consecutive samples exceeding the undercurrent limit that are required to trigger the ALERT function for
 /// each channel. The default is 1 sample [`SampleCount::_1`].
@@ -769,7 +967,7 @@ pub struct UcLimitNSamples {
     pub n_samples_ch4: SampleCount,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "1", bit_numbering = "lsb0")]
 /// Number of consecutive samples exceeding the overpower limit that are required to trigger the ALERT function for
 /// each channel. The default is 1 sample [`SampleCount::_1`].
@@ -785,7 +983,7 @@ pub struct OpLimitNSamples {
     pub n_samples_ch4: SampleCount,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "1", bit_numbering = "lsb0")]
 /// Number of consecutive samples exceeding the overvoltage limit that are required to trigger the ALERT function for
 /// each channel. The default is 1 sample [`SampleCount::_1`].
@@ -801,7 +999,7 @@ pub struct OvLimitNSamples {
     pub n_samples_ch4: SampleCount,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "1", bit_numbering = "lsb0")]
 /// Number of consecutive samples exceeding the undervoltage limit that are required to trigger the ALERT function for
 /// each channel. The default is 1 sample [`SampleCount::_1`].
@@ -817,7 +1015,46 @@ pub struct UvLimitNSamples {
     pub n_samples_ch4: SampleCount,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+/// Per-channel accessors for the consecutive sample count registers
+macro_rules! n_samples {
+    ($($type:ty),*) => {
+        $(
+            impl $type {
+                /// The sample count of channel `n` (1 to 4)
+                pub fn n_samples(&self, n: u8) -> SampleCount {
+                    match n {
+                        1 => self.n_samples_ch1,
+                        2 => self.n_samples_ch2,
+                        3 => self.n_samples_ch3,
+                        4 => self.n_samples_ch4,
+                        _ => panic!("Channel n must be between 1 and 4"),
+                    }
+                }
+
+                /// Sets the sample count of channel `n` (1 to 4)
+                pub fn set_n_samples(&mut self, n: u8, count: SampleCount) {
+                    match n {
+                        1 => self.n_samples_ch1 = count,
+                        2 => self.n_samples_ch2 = count,
+                        3 => self.n_samples_ch3 = count,
+                        4 => self.n_samples_ch4 = count,
+                        _ => panic!("Channel n must be between 1 and 4"),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+n_samples!(
+    OcLimitNSamples,
+    UcLimitNSamples,
+    OpLimitNSamples,
+    OvLimitNSamples,
+    UvLimitNSamples
+);
+
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "3", bit_numbering = "msb0")]
 /// Write to these bits to enable ALERT functions.
 /// To enable OC, UC, OP, OV, UV ALERTs, write ‘1’ to the appropriate bit. ALERTs must be enabled in this reg-
@@ -850,7 +1087,7 @@ pub struct AlertEnable {
     pub alert_cc: bool,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "1", bit_numbering = "lsb0")]
 /// This register contains an image of [`AccumConfig`] and reflects the current active values of these settings, whereas the
 /// values in register 25h may be programmed but not activated by one of the REFRESH commands. This register
@@ -866,7 +1103,7 @@ pub struct AccumConfigAct {
     pub acc4_config: AccumSetting,
 }
 
-#[derive(PackedStruct, Debug, PartialEq, Register)]
+#[derive(PackedStruct, Clone, Copy, Debug, PartialEq, Register)]
 #[packed_struct(size_bytes = "1", bit_numbering = "lsb0")]
 /// This register contains an image of [`AccumConfig`]
 /// The bits in this register reflect the value of these settings that were
@@ -886,6 +1123,28 @@ pub struct AccumConfigLat {
     pub acc4_config: AccumSetting,
 }
 
+/// Per-channel getters for [`AccumConfig`] and its images
+macro_rules! accum_getters {
+    ($($type:ty),*) => {
+        $(
+            impl $type {
+                /// What the accumulator of channel `n` (1 to 4) accumulates
+                pub fn accum(&self, n: u8) -> AccumSetting {
+                    match n {
+                        1 => self.acc1_config,
+                        2 => self.acc2_config,
+                        3 => self.acc3_config,
+                        4 => self.acc4_config,
+                        _ => panic!("Channel n must be between 1 and 4"),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+accum_getters!(AccumConfig, AccumConfigAct, AccumConfigLat);
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,237 @@
+//! Retrying failed bus transactions
+//!
+//! [`RetryI2c`] wraps an I2C bus and repeats transactions that fail with transient errors according to
+//! a [`RetryPolicy`], so a single glitch on a noisy bus doesn't abort a whole operation of the driver.
+//! Errors are classified with [`embedded_hal::i2c::Error::kind`]:
+//!
+//! - NACKs (the device is busy or didn't hear its address) are retried after a backoff
+//! - Arbitration loss (another controller won the bus) is retried immediately
+//! - Bus errors (misplaced START/STOP) are retried after a backoff
+//! - Overruns and other errors are returned immediately
+//!
+//! Note that a transaction that failed part way may still have had side effects on the device, for
+//! example a read of [`AlertStatus`](crate::regs::AlertStatus) clears the latched alerts.
+
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::{Error, ErrorKind, ErrorType, I2c, Operation};
+
+/// Which errors to retry and how often
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts of a transaction, including the first one
+    pub max_attempts: u8,
+    /// Delay before the first retry in microseconds, doubled for every further retry
+    pub backoff_us: u32,
+    /// Retry transactions that were not acknowledged by the device
+    pub retry_nack: bool,
+    /// Retry transactions that lost arbitration
+    pub retry_arbitration_loss: bool,
+    /// Retry transactions that failed with a bus error
+    pub retry_bus_error: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff_us: 100,
+            retry_nack: true,
+            retry_arbitration_loss: true,
+            retry_bus_error: true,
+        }
+    }
+}
+
+/// What to do about a failed transaction
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    /// Retry after the backoff delay
+    Backoff,
+    /// Retry right away
+    Immediate,
+    /// Return the error
+    Fail,
+}
+
+impl RetryPolicy {
+    fn action(&self, kind: ErrorKind) -> Action {
+        match kind {
+            ErrorKind::NoAcknowledge(_) if self.retry_nack => Action::Backoff,
+            ErrorKind::ArbitrationLoss if self.retry_arbitration_loss => Action::Immediate,
+            ErrorKind::Bus if self.retry_bus_error => Action::Backoff,
+            _ => Action::Fail,
+        }
+    }
+}
+
+/// A delay that doesn't wait, for retrying without backoff
+pub struct NoDelay;
+
+impl DelayNs for NoDelay {
+    fn delay_ns(&mut self, _ns: u32) {}
+}
+
+/// An I2C bus that retries failed transactions according to a [`RetryPolicy`]
+pub struct RetryI2c<I, D = NoDelay> {
+    i2c: I,
+    delay: D,
+    policy: RetryPolicy,
+    retried: u32,
+}
+
+impl<I> RetryI2c<I, NoDelay> {
+    /// Wraps `i2c`, retrying without any delay between attempts
+    pub fn new(i2c: I, policy: RetryPolicy) -> Self {
+        Self::with_delay(i2c, policy, NoDelay)
+    }
+}
+
+impl<I, D> RetryI2c<I, D> {
+    /// Wraps `i2c`, waiting on `delay` between attempts as configured by `policy`
+    pub fn with_delay(i2c: I, policy: RetryPolicy, delay: D) -> Self {
+        Self {
+            i2c,
+            delay,
+            policy,
+            retried: 0,
+        }
+    }
+
+    /// The number of transactions that needed at least one retry, successful or not
+    pub fn retried_transactions(&self) -> u32 {
+        self.retried
+    }
+
+    /// Resets the count of retried transactions
+    pub fn reset_retried_transactions(&mut self) {
+        self.retried = 0;
+    }
+
+    /// Returns the wrapped bus and delay
+    pub fn destroy(self) -> (I, D) {
+        (self.i2c, self.delay)
+    }
+}
+
+impl<I: I2c, D> ErrorType for RetryI2c<I, D> {
+    type Error = I::Error;
+}
+
+impl<I: I2c, D: DelayNs> I2c for RetryI2c<I, D> {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let mut backoff_us = self.policy.backoff_us;
+        let mut attempt = 1;
+        loop {
+            let err = match self.i2c.transaction(address, operations) {
+                Ok(()) => return Ok(()),
+                Err(err) => err,
+            };
+            let action = self.policy.action(err.kind());
+            if action == Action::Fail || attempt >= self.policy.max_attempts {
+                return Err(err);
+            }
+            if attempt == 1 {
+                self.retried = self.retried.saturating_add(1);
+            }
+            if action == Action::Backoff {
+                self.delay.delay_us(backoff_us);
+                backoff_us = backoff_us.saturating_mul(2);
+            }
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimBus;
+    use crate::{AddrSelect, PAC194X};
+    use embedded_hal::i2c::NoAcknowledgeSource;
+    use std::{vec, vec::Vec};
+
+    const ADDR: u8 = AddrSelect::GND as u8;
+
+    /// Fails transactions with the queued errors before passing them on to the simulated bus
+    struct Flaky {
+        bus: SimBus,
+        errors: Vec<ErrorKind>,
+    }
+
+    impl ErrorType for Flaky {
+        type Error = ErrorKind;
+    }
+
+    impl I2c for Flaky {
+        fn transaction(
+            &mut self,
+            address: u8,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), ErrorKind> {
+            if self.errors.is_empty() {
+                self.bus.transaction(address, operations)
+            } else {
+                Err(self.errors.remove(0))
+            }
+        }
+    }
+
+    /// Records every delay
+    #[derive(Default)]
+    struct Delays(Vec<u32>);
+
+    impl DelayNs for Delays {
+        fn delay_ns(&mut self, ns: u32) {
+            self.0.push(ns);
+        }
+    }
+
+    fn flaky(errors: Vec<ErrorKind>) -> Flaky {
+        Flaky {
+            bus: SimBus::new(&[ADDR]),
+            errors,
+        }
+    }
+
+    #[test]
+    fn classification() {
+        let nack = ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address);
+        let mut bus = RetryI2c::with_delay(
+            flaky(vec![nack, ErrorKind::ArbitrationLoss, ErrorKind::Bus]),
+            RetryPolicy {
+                max_attempts: 4,
+                ..Default::default()
+            },
+            Delays::default(),
+        );
+        let mut buf = [0];
+        bus.write_read(ADDR, &[0xFE], &mut buf).unwrap();
+        assert_eq!(buf, [0x54]);
+        assert_eq!(bus.retried_transactions(), 1);
+        // No backoff after the arbitration loss
+        assert_eq!(bus.destroy().1.0, vec![100_000, 200_000]);
+    }
+
+    #[test]
+    fn fails_fast_and_gives_up() {
+        let mut bus = RetryI2c::new(flaky(vec![ErrorKind::Overrun]), RetryPolicy::default());
+        assert_eq!(bus.write(ADDR, &[0x00]), Err(ErrorKind::Overrun));
+        assert_eq!(bus.retried_transactions(), 0);
+
+        let mut bus = RetryI2c::new(flaky(vec![ErrorKind::Bus; 3]), RetryPolicy::default());
+        assert_eq!(bus.write(ADDR, &[0x00]), Err(ErrorKind::Bus));
+        assert_eq!(bus.retried_transactions(), 1);
+    }
+
+    #[test]
+    fn driver_over_retrying_bus() {
+        let bus = RetryI2c::new(flaky(vec![ErrorKind::Bus]), RetryPolicy::default());
+        let mut sensor = PAC194X::new(bus, AddrSelect::GND).unwrap();
+        assert_eq!(sensor.manufacturer_id().unwrap(), 0x54);
+        assert_eq!(sensor.bus().retried_transactions(), 1);
+    }
+}
@@ -0,0 +1,205 @@
+//! Discovering the devices on a bus
+//!
+//! [`scan`] probes each of the 16 [`AddrSelect`] addresses and identifies the responders by their
+//! Product ID, Manufacturer ID and Revision ID registers.
+//!
+//! Two parts strapped to the same address answer at the same time, and as I2C is open drain the bus
+//! sees the AND of their responses. Such a conflict is only detectable when the combined IDs don't
+//! decode to a known part or change between reads, so identical parts sharing an address look like a
+//! single device.
+
+use crate::{AddrSelect, Error, PAC194X, ProductId};
+use embedded_hal::i2c::{Error as _, ErrorKind, I2c};
+
+/// The Manufacturer ID of Microchip
+pub const MICROCHIP_ID: u8 = 0x54;
+
+/// A supported part found on the bus
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Device {
+    pub addr_sel: AddrSelect,
+    pub product_id: ProductId,
+    pub revision_id: u8,
+}
+
+impl Device {
+    /// The number of channels of the part
+    pub fn channels(&self) -> u8 {
+        self.product_id.channels()
+    }
+
+    /// The full scale bus voltage of the part in microvolts
+    pub fn max_voltage_uv(&self) -> u32 {
+        self.product_id.max_voltage_uv()
+    }
+}
+
+/// What answered at an address
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Responder {
+    /// A PAC194X or PAC195X
+    Device(Device),
+    /// A Microchip device with an unknown Product ID, or with IDs that changed between reads.
+    /// This usually means several parts share the address.
+    Conflict {
+        product_id: u8,
+        manufacturer_id: u8,
+        revision_id: u8,
+    },
+    /// A device that doesn't identify as Microchip
+    Foreign { manufacturer_id: u8 },
+}
+
+/// The result of a [`scan`], indexed by address
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scan {
+    responders: [Option<Responder>; 16],
+}
+
+impl Scan {
+    /// What answered at `addr_sel`, `None` if the address wasn't acknowledged
+    pub fn get(&self, addr_sel: AddrSelect) -> Option<&Responder> {
+        self.responders[addr_sel as usize - AddrSelect::GND as usize].as_ref()
+    }
+
+    /// Every responding address, in order of I2C address
+    pub fn iter(&self) -> impl Iterator<Item = (AddrSelect, &Responder)> {
+        AddrSelect::ALL
+            .into_iter()
+            .zip(self.responders.iter())
+            .filter_map(|(a, r)| Some((a, r.as_ref()?)))
+    }
+
+    /// The supported parts found on the bus
+    pub fn devices(&self) -> impl Iterator<Item = &Device> {
+        self.iter().filter_map(|(_, r)| match r {
+            Responder::Device(device) => Some(device),
+            _ => None,
+        })
+    }
+
+    /// Whether any address had conflicting or non-Microchip responders
+    pub fn has_problems(&self) -> bool {
+        self.iter().any(|(_, r)| !matches!(r, Responder::Device(_)))
+    }
+}
+
+/// The raw identification registers of a device
+#[derive(PartialEq)]
+struct Ids {
+    product_id: u8,
+    manufacturer_id: u8,
+    revision_id: u8,
+}
+
+fn read_ids<I: I2c>(sensor: &mut PAC194X<I>) -> Result<Ids, Error<I::Error>> {
+    let manufacturer_id = sensor.manufacturer_id()?;
+    let product_id = match sensor.product_id() {
+        Ok(id) => id as u8,
+        Err(Error::UnknownProductId(raw)) => raw,
+        Err(e) => return Err(e),
+    };
+    Ok(Ids {
+        product_id,
+        manufacturer_id,
+        revision_id: sensor.revision_id()?,
+    })
+}
+
+/// Probes the device at `addr_sel`, `None` if the address isn't acknowledged
+pub fn probe<I: I2c>(
+    i2c: &mut I,
+    addr_sel: AddrSelect,
+) -> Result<Option<Responder>, Error<I::Error>> {
+    // The product ID is only a placeholder, the driver is only used to read the ID registers
+    let mut sensor = PAC194X::new_with_product_id(&mut *i2c, addr_sel, ProductId::PAC1941_1);
+    let ids = match read_ids(&mut sensor) {
+        Ok(ids) => ids,
+        Err(Error::I2c(e)) if matches!(e.kind(), ErrorKind::NoAcknowledge(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    if ids.manufacturer_id != MICROCHIP_ID {
+        return Ok(Some(Responder::Foreign {
+            manufacturer_id: ids.manufacturer_id,
+        }));
+    }
+    let conflict = Responder::Conflict {
+        product_id: ids.product_id,
+        manufacturer_id: ids.manufacturer_id,
+        revision_id: ids.revision_id,
+    };
+    if read_ids(&mut sensor)? != ids {
+        return Ok(Some(conflict));
+    }
+    Ok(Some(match ProductId::from_raw(ids.product_id) {
+        Some(product_id) => Responder::Device(Device {
+            addr_sel,
+            product_id,
+            revision_id: ids.revision_id,
+        }),
+        None => conflict,
+    }))
+}
+
+/// Probes every [`AddrSelect`] address on the bus.
+///
+/// Addresses that aren't acknowledged are skipped, any other bus error aborts the scan. Note that
+/// identifying a responder writes its register pointer, which may upset non-Microchip devices.
+pub fn scan<I: I2c>(i2c: &mut I) -> Result<Scan, Error<I::Error>> {
+    let mut responders = [None; 16];
+    for (responder, addr_sel) in responders.iter_mut().zip(AddrSelect::ALL) {
+        *responder = probe(i2c, addr_sel)?;
+    }
+    Ok(Scan { responders })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regs::Address;
+    use crate::sim::SimBus;
+    use std::vec::Vec;
+
+    #[test]
+    fn finds_devices_and_flags_problems() {
+        let mut bus = SimBus::new(&[0x10, 0x12, 0x15, 0x1F]);
+        bus.with(0x12, |dev| dev.set(Address::ProductId, &[0b0111_1001]));
+        // Two different parts ANDed together
+        bus.with(0x15, |dev| dev.set(Address::ProductId, &[0b0110_0000]));
+        bus.with(0x1F, |dev| dev.set(Address::ManufacturerId, &[0x5D]));
+
+        let scan = scan(&mut bus).unwrap();
+        let found: Vec<_> = scan.iter().map(|(a, _)| a).collect();
+        assert_eq!(
+            found,
+            [
+                AddrSelect::GND,
+                AddrSelect::_806,
+                AddrSelect::_3240,
+                AddrSelect::VDD
+            ]
+        );
+        let devices: Vec<_> = scan.devices().collect();
+        assert_eq!(devices.len(), 2);
+        assert_eq!(devices[0].product_id, ProductId::PAC1944_1);
+        assert_eq!(devices[0].channels(), 4);
+        assert_eq!(devices[1].addr_sel, AddrSelect::_806);
+        assert_eq!(devices[1].max_voltage_uv(), 32_000_000);
+        assert_eq!(devices[1].revision_id, 0b10);
+        assert!(matches!(
+            scan.get(AddrSelect::_3240),
+            Some(Responder::Conflict {
+                product_id: 0b0110_0000,
+                ..
+            })
+        ));
+        assert_eq!(
+            scan.get(AddrSelect::VDD),
+            Some(&Responder::Foreign {
+                manufacturer_id: 0x5D
+            })
+        );
+        assert!(scan.get(AddrSelect::_499).is_none());
+        assert!(scan.has_problems());
+    }
+}
@@ -0,0 +1,242 @@
+//! A simulated I2C bus of PAC194X devices, used by the tests
+
+use crate::regs::Address;
+use embedded_hal::i2c::{ErrorKind, ErrorType, I2c, NoAcknowledgeSource, Operation};
+use std::{cell::RefCell, collections::BTreeMap, rc::Rc, vec, vec::Vec};
+
+/// Size of the register at `addr` in bytes, 0 if there is no register at that address
+fn size(addr: u8) -> usize {
+    match addr {
+        0x01 => 2,
+        0x02 => 4,
+        0x03..=0x06 => 7,
+        0x07..=0x16 => 2,
+        0x17..=0x1A => 4,
+        0x1C => 1,
+        0x1D => 2,
+        0x20 => 1,
+        0x21..=0x24 => 2,
+        0x25 => 1,
+        0x26..=0x28 => 3,
+        0x29 => 2,
+        0x30..=0x37 => 2,
+        0x38..=0x3B => 3,
+        0x3C..=0x43 => 2,
+        0x44..=0x48 => 1,
+        0x49 => 3,
+        0x4A..=0x4B => 1,
+        0xFD..=0xFF => 1,
+        _ => 0,
+    }
+}
+
+/// The channel the result register at `addr` belongs to
+fn channel(addr: u8) -> Option<u8> {
+    match addr {
+        0x03..=0x1A => Some((addr - 0x03) % 4 + 1),
+        _ => None,
+    }
+}
+
+/// Whether `addr` holds a limit, N-samples or fullness limit register
+fn is_limit(addr: u8) -> bool {
+    addr == Address::AccFullnessLimits as u8
+        || (Address::OcLimitn as u8..Address::AlertEnable as u8).contains(&addr)
+}
+
+/// The register file of a single simulated device
+pub(crate) struct SimPac {
+    regs: Vec<Vec<u8>>,
+    pointer: u8,
+    /// The ALERT_ENABLE activated by the most recent REFRESH, which has no readable image
+    alert_enable_act: Vec<u8>,
+    /// Each write to a limit, N-samples or fullness limit register, with the active ALERT_ENABLE
+    limit_writes: Vec<(u8, Vec<u8>)>,
+}
+
+impl SimPac {
+    fn new() -> Self {
+        let mut regs: Vec<Vec<u8>> = (0..=255u8).map(|a| vec![0; size(a)]).collect();
+        // POR default of SMBUS_SETTINGS, PAC1944-1 identification
+        regs[Address::SmbusSettings as usize] = vec![0x10];
+        regs[Address::ProductId as usize] = vec![0b0110_1011];
+        regs[Address::ManufacturerId as usize] = vec![0x54];
+        regs[Address::RevisionId as usize] = vec![0b10];
+        Self {
+            regs,
+            pointer: 0,
+            alert_enable_act: vec![0; 3],
+            limit_writes: Vec::new(),
+        }
+    }
+
+    /// The register addresses written to with the ALERT_ENABLE that was active at the time
+    pub(crate) fn limit_writes(&self) -> &[(u8, Vec<u8>)] {
+        &self.limit_writes
+    }
+
+    /// Sets the raw contents of the register at `addr`
+    pub(crate) fn set(&mut self, addr: Address, bytes: &[u8]) {
+        self.set_raw(addr as u8, bytes);
+    }
+
+    /// Sets the raw contents of the register of channel `n` at `addr`
+    pub(crate) fn set_n(&mut self, addr: Address, n: u8, bytes: &[u8]) {
+        self.set_raw(addr as u8 + n - 1, bytes);
+    }
+
+    fn set_raw(&mut self, addr: u8, bytes: &[u8]) {
+        assert_eq!(bytes.len(), size(addr), "register {addr:#04x} size");
+        self.regs[addr as usize].copy_from_slice(bytes);
+    }
+
+    fn is_skipped(&self, addr: u8) -> bool {
+        let no_skip = self.regs[Address::SmbusSettings as usize][0] & 0b10 != 0;
+        !no_skip && channel(addr).is_some_and(|n| self.is_off(n))
+    }
+
+    fn is_off(&self, n: u8) -> bool {
+        self.regs[Address::CtrlAct as usize][1] & (0x80 >> (n - 1)) != 0
+    }
+
+    /// Advances the address pointer to the next register, skipping inactive channels
+    fn advance(&mut self) {
+        loop {
+            self.pointer = self.pointer.wrapping_add(1);
+            if size(self.pointer) > 0 && !self.is_skipped(self.pointer) {
+                return;
+            }
+        }
+    }
+
+    fn refresh(&mut self) {
+        for (reg, act, lat) in [
+            (Address::Ctrl, Address::CtrlAct, Address::CtrlLat),
+            (
+                Address::NegPwrFsr,
+                Address::NegPwrFsrAct,
+                Address::NegPwrFsrLat,
+            ),
+            (
+                Address::AccumConfig,
+                Address::AccumConfigAct,
+                Address::AccumConfigLat,
+            ),
+        ] {
+            let (reg, act, lat) = (reg as usize, act as usize, lat as usize);
+            self.regs[lat] = self.regs[act].clone();
+            self.regs[act] = self.regs[reg].clone();
+        }
+        self.alert_enable_act = self.regs[Address::AlertEnable as usize].clone();
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.pointer = bytes[0];
+        if bytes.len() == 1 {
+            if bytes[0] == Address::Refresh as u8 || bytes[0] == Address::RefreshV as u8 {
+                self.refresh();
+            }
+            return;
+        }
+        let mut data = bytes[1..].iter();
+        let mut addr = self.pointer;
+        'regs: loop {
+            for i in 0..size(addr) {
+                let Some(byte) = data.next() else {
+                    break 'regs;
+                };
+                if i == 0 && is_limit(addr) {
+                    self.limit_writes
+                        .push((addr, self.alert_enable_act.clone()));
+                }
+                self.regs[addr as usize][i] = *byte;
+            }
+            addr = addr.wrapping_add(1);
+        }
+    }
+
+    fn read(&mut self, buf: &mut [u8]) {
+        let mut bytes = buf.iter_mut();
+        loop {
+            let off = channel(self.pointer).is_some_and(|n| self.is_off(n));
+            for i in 0..size(self.pointer) {
+                let Some(byte) = bytes.next() else {
+                    return;
+                };
+                *byte = if off {
+                    0xFF
+                } else {
+                    self.regs[self.pointer as usize][i]
+                };
+            }
+            // Reading ALERT_STATUS clears it
+            if self.pointer == Address::AlertStatus as u8 {
+                self.regs[self.pointer as usize].fill(0);
+            }
+            self.advance();
+        }
+    }
+}
+
+/// A shared handle to a simulated bus, cloned to give the driver its own handle
+#[derive(Clone)]
+pub(crate) struct SimBus {
+    devices: Rc<RefCell<BTreeMap<u8, SimPac>>>,
+}
+
+impl SimBus {
+    /// Creates a bus with a device at each of `addresses`
+    pub(crate) fn new(addresses: &[u8]) -> Self {
+        Self {
+            devices: Rc::new(RefCell::new(
+                addresses.iter().map(|a| (*a, SimPac::new())).collect(),
+            )),
+        }
+    }
+
+    /// Runs `f` on the device at `addr`
+    pub(crate) fn with<R>(&self, addr: u8, f: impl FnOnce(&mut SimPac) -> R) -> R {
+        f(self.devices.borrow_mut().get_mut(&addr).unwrap())
+    }
+}
+
+impl ErrorType for SimBus {
+    type Error = ErrorKind;
+}
+
+impl I2c for SimBus {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let mut devices = self.devices.borrow_mut();
+        if address == 0 {
+            for op in operations {
+                if let Operation::Write([cmd]) = op
+                    && *cmd == Address::RefreshG as u8
+                {
+                    devices.values_mut().for_each(SimPac::refresh);
+                }
+            }
+            return Ok(());
+        }
+        let device = devices
+            .get_mut(&address)
+            .ok_or(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address))?;
+        // Block reads (as opposed to receive byte) start with the byte count if BYTE_COUNT is set
+        let byte_count = device.regs[Address::SmbusSettings as usize][0] & 0b100 != 0
+            && matches!(operations, [Operation::Write(_), Operation::Read(_)]);
+        for op in operations {
+            match op {
+                Operation::Write(bytes) => device.write(bytes),
+                Operation::Read(buf) if byte_count => {
+                    buf[0] = size(device.pointer) as u8;
+                    device.read(&mut buf[1..]);
+                }
+                Operation::Read(buf) => device.read(buf),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+//! Transport for SMBus-only adapters
+//!
+//! Some I2C controllers (and Linux adapters) can only perform SMBus transactions and not arbitrary
+//! I2C `write_read`s. [`SmbusI2c`] implements the embedded-hal [`I2c`] trait on top of the handful of
+//! SMBus operations the driver needs, so it can be used with [`PAC194X`] like any other bus. Block reads
+//! are performed as SMBus Block Reads, which requires [`SmbusSettings::byte_count`] to be set on the device.
+//! [`PAC194X::new_smbus`] takes care of that, and [`SmbusI2c`] refuses writes that would clear it.
+//!
+//! [`SmbusSettings::byte_count`]: crate::regs::SmbusSettings::byte_count
+
+use crate::regs::Address;
+use crate::{AddrSelect, Error, PAC194X};
+use embedded_hal::i2c::{self, ErrorKind, ErrorType, I2c, Operation};
+
+/// Maximum number of data bytes in an SMBus block transfer
+pub const BLOCK_MAX: usize = 32;
+
+/// The SMBus operations used by the driver
+pub trait Smbus {
+    type Error: i2c::Error;
+
+    /// SMBus Send Byte, sets the register pointer or sends a command such as REFRESH
+    fn send_byte(&mut self, address: u8, command: u8) -> Result<(), Self::Error>;
+
+    /// SMBus Receive Byte, reads a byte from the current register pointer
+    fn receive_byte(&mut self, address: u8) -> Result<u8, Self::Error>;
+
+    /// Writes `data` to the registers starting at `command`, without a byte count (I2C Block Write)
+    fn write_block(&mut self, address: u8, command: u8, data: &[u8]) -> Result<(), Self::Error>;
+
+    /// SMBus Block Read of the registers starting at `command`.
+    ///
+    /// The data, without the byte count, is written into `buf` and the byte count is returned.
+    fn read_block(
+        &mut self,
+        address: u8,
+        command: u8,
+        buf: &mut [u8; BLOCK_MAX],
+    ) -> Result<usize, Self::Error>;
+}
+
+/// An [`I2c`] bus built from [`Smbus`] operations
+pub struct SmbusI2c<S> {
+    smbus: S,
+}
+
+impl<S> SmbusI2c<S> {
+    pub fn new(smbus: S) -> Self {
+        Self { smbus }
+    }
+
+    /// Returns the underlying SMBus
+    pub fn destroy(self) -> S {
+        self.smbus
+    }
+}
+
+/// Errors of [`SmbusI2c`]
+#[derive(Debug, PartialEq)]
+pub enum SmbusError<E> {
+    /// Error of the underlying SMBus
+    Smbus(E),
+    /// The transaction can't be expressed with SMBus operations
+    Unsupported,
+    /// The write would clear [`SmbusSettings::byte_count`](crate::regs::SmbusSettings::byte_count),
+    /// without which SMBus Block Reads can't be performed
+    ByteCountRequired,
+}
+
+impl<E: i2c::Error> i2c::Error for SmbusError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Smbus(e) => e.kind(),
+            Self::Unsupported | Self::ByteCountRequired => ErrorKind::Other,
+        }
+    }
+}
+
+impl<S: Smbus> ErrorType for SmbusI2c<S> {
+    type Error = SmbusError<S::Error>;
+}
+
+impl<S: Smbus> I2c for SmbusI2c<S> {
+    fn transaction(
+        &mut self,
+        address: u8,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        match operations {
+            [Operation::Write([command])] => self.smbus.send_byte(address, *command),
+            [Operation::Write([command, data @ ..])] => {
+                if *command == Address::SmbusSettings as u8 && data[0] & 0b100 == 0 {
+                    return Err(SmbusError::ByteCountRequired);
+                }
+                self.smbus.write_block(address, *command, data)
+            }
+            [Operation::Read([byte])] => self.smbus.receive_byte(address).map(|b| *byte = b),
+            // The response of a block read is presented as it appears on the wire, byte count first
+            [Operation::Write([command]), Operation::Read(buf)] if !buf.is_empty() => {
+                let mut data = [0u8; BLOCK_MAX];
+                self.smbus
+                    .read_block(address, *command, &mut data)
+                    .map(|count| {
+                        buf[0] = count as u8;
+                        let len = count.min(buf.len() - 1);
+                        buf[1..=len].copy_from_slice(&data[..len]);
+                    })
+            }
+            _ => return Err(SmbusError::Unsupported),
+        }
+        .map_err(SmbusError::Smbus)
+    }
+}
+
+impl<S: Smbus> PAC194X<SmbusI2c<S>> {
+    /// Initializes the driver on an SMBus-only adapter and enables BYTE_COUNT mode for block reads
+    pub fn new_smbus(smbus: S, addr_sel: AddrSelect) -> Result<Self, Error<SmbusError<S::Error>>> {
+        let mut s = Self::new(SmbusI2c::new(smbus), addr_sel)?;
+        s.enable_byte_count(true)?;
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::SimBus;
+
+    const ADDR: u8 = AddrSelect::GND as u8;
+
+    /// An SMBus backed by the simulated bus
+    struct SimSmbus(SimBus);
+
+    impl Smbus for SimSmbus {
+        type Error = ErrorKind;
+
+        fn send_byte(&mut self, address: u8, command: u8) -> Result<(), ErrorKind> {
+            self.0.write(address, &[command])
+        }
+
+        fn receive_byte(&mut self, address: u8) -> Result<u8, ErrorKind> {
+            let mut buf = [0];
+            self.0.read(address, &mut buf)?;
+            Ok(buf[0])
+        }
+
+        fn write_block(&mut self, address: u8, command: u8, data: &[u8]) -> Result<(), ErrorKind> {
+            let mut bytes = std::vec![command];
+            bytes.extend_from_slice(data);
+            self.0.write(address, &bytes)
+        }
+
+        fn read_block(
+            &mut self,
+            address: u8,
+            command: u8,
+            buf: &mut [u8; BLOCK_MAX],
+        ) -> Result<usize, ErrorKind> {
+            // Read the byte count, then the whole block
+            let mut count = [0];
+            self.0.write_read(address, &[command], &mut count)?;
+            let mut response = [0u8; BLOCK_MAX + 1];
+            let len = count[0] as usize;
+            self.0
+                .write_read(address, &[command], &mut response[..len + 1])?;
+            buf[..len].copy_from_slice(&response[1..=len]);
+            Ok(len)
+        }
+    }
+
+    #[test]
+    fn byte_count_block_reads() {
+        let bus = SimBus::new(&[ADDR]);
+        bus.with(ADDR, |dev| {
+            dev.set_n(Address::Vbusn, 3, &[0x12, 0x34]);
+            dev.set(Address::CtrlAct, &[0, 0b0100_0000]);
+        });
+        let mut sensor = PAC194X::new_smbus(SimSmbus(bus.clone()), AddrSelect::GND).unwrap();
+        assert!(sensor.read_smub_settings().unwrap().byte_count);
+        assert_eq!(sensor.read_vbusn(3).unwrap().voltage, 0x1234);
+        let m = sensor.read_measurements().unwrap();
+        assert!(m.channel(2).is_none());
+        assert_eq!(m.channel(3).unwrap().vbus.voltage, 0x1234);
+        assert_eq!(
+            sensor.enable_byte_count(false),
+            Err(Error::I2c(SmbusError::ByteCountRequired))
+        );
+        assert!(sensor.read_smub_settings().unwrap().byte_count);
+        assert_eq!(sensor.read_vbusn(3).unwrap().voltage, 0x1234);
+        let mut block = [0u8; BLOCK_MAX + 1];
+        assert_eq!(
+            sensor.read_register(Address::Vaccn as u8, &mut block),
+            Err(Error::BlockTooLong)
+        );
+    }
+
+    #[test]
+    fn write_config_keeps_byte_count() {
+        let bus = SimBus::new(&[ADDR]);
+        bus.with(ADDR, |dev| dev.set_n(Address::Vbusn, 1, &[0x12, 0x34]));
+        let mut sensor = PAC194X::new_smbus(SimSmbus(bus), AddrSelect::GND).unwrap();
+        // The power-on defaults have BYTE_COUNT clear
+        sensor.monitor_por(crate::Config::default()).unwrap();
+        let settings = sensor.read_smub_settings().unwrap();
+        assert!(settings.byte_count);
+        assert!(!settings.por);
+        assert_eq!(sensor.read_vbusn(1).unwrap().voltage, 0x1234);
+    }
+
+    #[test]
+    fn byte_count_with_raw_i2c() {
+        let bus = SimBus::new(&[ADDR]);
+        bus.with(ADDR, |dev| dev.set_n(Address::Vsensen, 4, &[0xAB, 0xCD]));
+        let mut sensor = PAC194X::new(bus, AddrSelect::GND).unwrap();
+        sensor.enable_byte_count(true).unwrap();
+        assert_eq!(sensor.read_vsensen(4).unwrap().voltage, 0xABCD);
+        sensor.enable_byte_count(false).unwrap();
+        assert_eq!(sensor.read_vsensen(4).unwrap().voltage, 0xABCD);
+        assert!(!sensor.read_smub_settings().unwrap().byte_count);
+    }
+}
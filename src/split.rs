@@ -0,0 +1,173 @@
+//! Energy and charge split by direction
+//!
+//! With a bipolar VBUS or VSENSE range, power and current can be negative, for example while a motor
+//! regenerates or a battery charges, but the accumulators only sum them into a net value. A [`Split`]
+//! instead samples the signed VPOWER and VSENSE results of each channel at a fixed period and keeps the
+//! positive and negative energy and charge apart.
+//!
+//! [`PAC194X::sample_split`] takes one sample: call it every [`Split::period_us`] microseconds, for example
+//! from a timer. Measurements read elsewhere at that period can be added with [`Split::record`]. Energy
+//! and charge are kept in picojoules and picocoulombs, the products of microwatts or microamps and
+//! microseconds, so short periods don't round away.
+
+use crate::measurements::Measurements;
+use crate::{Error, PAC194X};
+use embedded_hal::delay::DelayNs;
+use embedded_hal::i2c::I2c;
+
+/// The totals of a channel by direction, as magnitudes
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SplitTotals {
+    /// Energy in picojoules while the power was positive
+    pub positive_pj: u128,
+    /// Energy in picojoules while the power was negative
+    pub negative_pj: u128,
+    /// Charge in picocoulombs while the current was positive
+    pub positive_pc: u128,
+    /// Charge in picocoulombs while the current was negative
+    pub negative_pc: u128,
+}
+
+impl SplitTotals {
+    /// The net energy in picojoules
+    pub fn net_pj(&self) -> i128 {
+        self.positive_pj as i128 - self.negative_pj as i128
+    }
+
+    /// The net charge in picocoulombs
+    pub fn net_pc(&self) -> i128 {
+        self.positive_pc as i128 - self.negative_pc as i128
+    }
+
+    fn add(&mut self, power_uw: i64, current_ua: i64, period_us: u32) {
+        let period = period_us as u128;
+        let energy = power_uw.unsigned_abs() as u128 * period;
+        let charge = current_ua.unsigned_abs() as u128 * period;
+        if power_uw >= 0 {
+            self.positive_pj += energy;
+        } else {
+            self.negative_pj += energy;
+        }
+        if current_ua >= 0 {
+            self.positive_pc += charge;
+        } else {
+            self.negative_pc += charge;
+        }
+    }
+}
+
+/// Energy and charge totals of the channels of a device, split by direction
+#[derive(Debug, Clone, PartialEq)]
+pub struct Split {
+    /// Sense resistor of each channel in micro-ohms, 0 for channels that aren't split
+    shunts_uohm: [u32; 4],
+    period_us: u32,
+    totals: [SplitTotals; 4],
+    samples: u64,
+}
+
+impl Split {
+    /// Splits the channels with sense resistors of `shunts_uohm` micro-ohms (0 for channels that aren't
+    /// split), sampled every `period_us` microseconds
+    pub fn new(shunts_uohm: [u32; 4], period_us: u32) -> Self {
+        Self {
+            shunts_uohm,
+            period_us,
+            totals: [SplitTotals::default(); 4],
+            samples: 0,
+        }
+    }
+
+    /// The sample period in microseconds
+    pub fn period_us(&self) -> u32 {
+        self.period_us
+    }
+
+    /// The totals of channel `n` (1 to 4)
+    pub fn totals(&self, n: u8) -> SplitTotals {
+        assert!((1..=4).contains(&n), "Channel n must be between 1 and 4");
+        self.totals[(n - 1) as usize]
+    }
+
+    /// The number of samples recorded
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+
+    /// Adds the VPOWER and VSENSE results of `m`, each standing for one period. Disabled channels are
+    /// skipped.
+    pub fn record(&mut self, m: &Measurements) {
+        for n in 1..=4u8 {
+            let i = (n - 1) as usize;
+            let shunt = self.shunts_uohm[i];
+            if shunt == 0 {
+                continue;
+            }
+            if let (Some(power), Some(current)) = (m.power_uw(n, shunt), m.current_ua(n, shunt)) {
+                self.totals[i].add(power, current, self.period_us);
+            }
+        }
+        self.samples += 1;
+    }
+}
+
+impl<E, I> PAC194X<I>
+where
+    I: I2c<Error = E>,
+{
+    /// Takes one sample of every channel into `split`
+    ///
+    /// Issues REFRESH_V, which leaves the accumulators running, waits 1 ms on `delay` and reads the
+    /// results.
+    pub fn sample_split<D: DelayNs>(
+        &mut self,
+        split: &mut Split,
+        delay: &mut D,
+    ) -> Result<(), Error<E>> {
+        self.refresh_v()?;
+        delay.delay_ms(1);
+        split.record(&self.read_measurements()?);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AddrSelect;
+    use crate::regs::{Address, VSenseFSR};
+    use crate::sim::SimBus;
+    use embedded_hal_mock::eh1::delay::NoopDelay;
+
+    #[test]
+    fn split_by_sign() {
+        let bus = SimBus::new(&[0x10]);
+        let mut sensor = PAC194X::new(bus, AddrSelect::GND).unwrap();
+        sensor
+            .batch(|s| s.modify_neg_pwr_fsr(|fsr| fsr.set_vsense_fsr(1, VSenseFSR::BipolarHV)))
+            .unwrap();
+        let mut split = Split::new([10_000, 0, 0, 0], 1000);
+        // +/-45 W and +/-2.5 A with a 9 V unipolar VBUS and a 100 mV bipolar VSENSE through 10 mΩ
+        let set = |sensor: &mut PAC194X<SimBus>, power: u32, vsense: u16| {
+            sensor.i2c.with(0x10, |dev| {
+                dev.set_n(Address::Vpowern, 1, &(power << 2).to_be_bytes());
+                dev.set_n(Address::Vsensen, 1, &vsense.to_be_bytes());
+            })
+        };
+        set(&mut sensor, 0x1000_0000, 0x2000);
+        sensor.sample_split(&mut split, &mut NoopDelay).unwrap();
+        sensor.sample_split(&mut split, &mut NoopDelay).unwrap();
+        set(&mut sensor, 0x3000_0000, 0xE000);
+        sensor.sample_split(&mut split, &mut NoopDelay).unwrap();
+
+        let totals = split.totals(1);
+        assert_eq!(totals.positive_pj, 90_000_000_000);
+        assert_eq!(totals.negative_pj, 45_000_000_000);
+        assert_eq!(totals.positive_pc, 5_000_000_000);
+        assert_eq!(totals.negative_pc, 2_500_000_000);
+        assert_eq!(totals.net_pj(), 45_000_000_000);
+        assert_eq!(totals.net_pc(), 2_500_000_000);
+        assert_eq!(split.totals(2), SplitTotals::default());
+        assert_eq!(split.samples(), 3);
+    }
+}
@@ -0,0 +1,330 @@
+//! Conversion timing of the sample modes
+//!
+//! In the continuous [`SampleMode`]s the PAC194X runs one conversion cycle per sample period, converting
+//! VBUS and VSENSE of every active channel in each cycle. The period is fixed by the mode: channels turned
+//! off in [`Ctrl`](crate::regs::Ctrl) are skipped, which leaves the cycle idle for longer without
+//! shortening it. Fast and Burst modes instead convert back to back, one channel every
+//! [`CHANNEL_CONVERSION_NS`], so each channel is converted more often when fewer are active: 1024 SPS with
+//! four channels up to 4096 SPS with one. The single-shot modes convert once, or 8 times averaged, per
+//! REFRESH.
+//!
+//! In the adaptive accumulation modes the accumulators and ACC_COUNT advance at 1024 SPS whatever the
+//! sample rate (see [`accumulation_rate`]), so the energy from an accumulator is its sum over 1024 per
+//! second in every adaptive mode. A limit ALERT that needs N consecutive samples over the limit (see
+//! [`SampleCount`]) triggers N sample periods after the limit is first crossed, which [`debounce`]
+//! converts to and from time.
+//!
+//! [`Timing`] gathers all of these for a mode and a number of active channels, and
+//! [`PAC194X::read_timing`] for the active settings of a device.
+
+use crate::regs::{Channels, SampleCount, SampleMode};
+use crate::{Error, PAC194X};
+use embedded_hal::i2c::I2c;
+
+/// The conversion time of VBUS and VSENSE of one channel in nanoseconds, a quarter of a 1024 SPS cycle
+pub const CHANNEL_CONVERSION_NS: u32 = 244_141;
+
+/// The time the results take to be valid after a REFRESH in microseconds
+pub const REFRESH_US: u32 = 1000;
+
+/// The sample rate of the continuous `mode` in samples per second, `None` for the modes without a fixed
+/// rate (single-shot, Fast, Burst and Sleep). See [`Timing`] for Fast and Burst.
+pub fn sample_rate(mode: SampleMode) -> Option<u32> {
+    match mode {
+        SampleMode::_1024Adaptive | SampleMode::_1024 => Some(1024),
+        SampleMode::_256Adaptive | SampleMode::_256 => Some(256),
+        SampleMode::_64Adaptive | SampleMode::_64 => Some(64),
+        SampleMode::_8Adaptive | SampleMode::_8 => Some(8),
+        SampleMode::SingleShot
+        | SampleMode::SingleShot8X
+        | SampleMode::Fast
+        | SampleMode::Burst
+        | SampleMode::Sleep => None,
+    }
+}
+
+/// The sample period of the continuous `mode` in microseconds, rounded to the nearest microsecond
+pub fn sample_period_us(mode: SampleMode) -> Option<u32> {
+    sample_rate(mode).map(|rate| (1_000_000 + rate / 2) / rate)
+}
+
+/// The rate in samples per second at which the accumulators and ACC_COUNT advance in the continuous `mode`
+///
+/// In the adaptive accumulation modes each conversion is accumulated, and counted, as many times as it
+/// would have been converted at 1024 SPS, so the accumulators advance at 1024 SPS whatever the sample rate.
+pub fn accumulation_rate(mode: SampleMode) -> Option<u32> {
+    match mode {
+        SampleMode::_1024Adaptive
+        | SampleMode::_256Adaptive
+        | SampleMode::_64Adaptive
+        | SampleMode::_8Adaptive => Some(1024),
+        _ => sample_rate(mode),
+    }
+}
+
+/// The timing of a sample mode with a number of active channels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing {
+    pub mode: SampleMode,
+    /// The channels converted in each cycle
+    pub active: u8,
+    /// The time between two conversions of a channel in nanoseconds, `None` unless the mode converts
+    /// continuously
+    pub period_ns: Option<u32>,
+    /// The conversions of a channel per second, rounded to the nearest integer
+    pub sample_rate: Option<u32>,
+    /// The samples per second the accumulators and ACC_COUNT advance by
+    pub accumulation_rate: Option<u32>,
+    /// The time from a REFRESH until the results are valid in microseconds, including the conversions of
+    /// the single-shot modes
+    pub ready_us: u32,
+}
+
+impl Timing {
+    /// The timing of `mode` with `active` channels (0 to 4) converted
+    pub fn new(mode: SampleMode, active: u8) -> Self {
+        let idle = Self {
+            mode,
+            active,
+            period_ns: None,
+            sample_rate: None,
+            accumulation_rate: None,
+            ready_us: REFRESH_US,
+        };
+        if active == 0 {
+            return idle;
+        }
+        // A cycle of the active channels, converted back to back at 4096 per second
+        let conversion_ns = ((active as u64 * 1_000_000_000 + 2048) / 4096) as u32;
+        match mode {
+            SampleMode::Fast | SampleMode::Burst => {
+                let rate = (4096 + active as u32 / 2) / active as u32;
+                Self {
+                    period_ns: Some(conversion_ns),
+                    sample_rate: Some(rate),
+                    accumulation_rate: Some(rate),
+                    ..idle
+                }
+            }
+            SampleMode::SingleShot | SampleMode::SingleShot8X => {
+                let cycles = if mode == SampleMode::SingleShot8X {
+                    8
+                } else {
+                    1
+                };
+                let conversion_us = (cycles * conversion_ns).div_ceil(1000);
+                Self {
+                    ready_us: conversion_us.max(REFRESH_US),
+                    ..idle
+                }
+            }
+            SampleMode::Sleep => idle,
+            _ => {
+                let rate = sample_rate(mode).unwrap();
+                Self {
+                    period_ns: Some((1_000_000_000 + rate / 2) / rate),
+                    sample_rate: Some(rate),
+                    accumulation_rate: accumulation_rate(mode),
+                    ..idle
+                }
+            }
+        }
+    }
+
+    /// The timing of `mode` on a part with `channels` channels, with the channels in `off` turned off
+    pub fn with_channels(mode: SampleMode, off: Channels, channels: u8) -> Self {
+        let active = (1..=channels).filter(|n| !off.get(*n)).count();
+        Self::new(mode, active as u8)
+    }
+
+    /// The sample rate, or [`Error::NoSampleRate`] in a mode without one
+    pub fn rate<E>(&self) -> Result<u32, Error<E>> {
+        self.sample_rate.ok_or(Error::NoSampleRate(self.mode))
+    }
+
+    /// The accumulation rate, or [`Error::NoSampleRate`] in a mode without one
+    pub fn acc_rate<E>(&self) -> Result<u32, Error<E>> {
+        self.accumulation_rate.ok_or(Error::NoSampleRate(self.mode))
+    }
+}
+
+/// The number of samples of a [`SampleCount`]
+pub fn samples(count: SampleCount) -> u8 {
+    match count {
+        SampleCount::_1 => 1,
+        SampleCount::_4 => 4,
+        SampleCount::_8 => 8,
+        SampleCount::_16 => 16,
+    }
+}
+
+/// A debounce time achieved with a [`SampleCount`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Debounce {
+    /// The consecutive samples over the limit required to trigger the ALERT
+    pub count: SampleCount,
+    /// The time those samples take in microseconds
+    pub effective_us: u32,
+}
+
+/// The [`SampleCount`] whose debounce time at `rate` samples per second is nearest to `debounce_us`
+/// microseconds, the shorter one on a tie
+pub fn debounce(debounce_us: u32, rate: u32) -> Debounce {
+    let time_us = |count| {
+        let us = samples(count) as u64 * 1_000_000;
+        ((us + rate as u64 / 2) / rate as u64) as u32
+    };
+    let count = [
+        SampleCount::_1,
+        SampleCount::_4,
+        SampleCount::_8,
+        SampleCount::_16,
+    ]
+    .into_iter()
+    .min_by_key(|count| time_us(*count).abs_diff(debounce_us))
+    .unwrap();
+    Debounce {
+        count,
+        effective_us: time_us(count),
+    }
+}
+
+impl<E, I> PAC194X<I>
+where
+    I: I2c<Error = E>,
+{
+    /// The timing of the active sample mode and channels in [`CtrlAct`](crate::regs::CtrlAct)
+    pub fn read_timing(&mut self) -> Result<Timing, Error<E>> {
+        let ctrl = self.read_ctrl_act()?;
+        let channels = self.product_id.channels();
+        Ok(Timing::with_channels(
+            ctrl.sample_mode,
+            ctrl.channel_n_off,
+            channels,
+        ))
+    }
+
+    /// The timing the latched results were converted with, from [`CtrlLat`](crate::regs::CtrlLat)
+    pub fn read_timing_lat(&mut self) -> Result<Timing, Error<E>> {
+        let ctrl = self.read_ctrl_lat()?;
+        let channels = self.product_id.channels();
+        Ok(Timing::with_channels(
+            ctrl.sample_mode,
+            ctrl.channel_n_off,
+            channels,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates() {
+        assert_eq!(sample_rate(SampleMode::_1024Adaptive), Some(1024));
+        assert_eq!(sample_rate(SampleMode::_8), Some(8));
+        assert_eq!(sample_rate(SampleMode::Sleep), None);
+        assert_eq!(sample_period_us(SampleMode::_1024), Some(977));
+        assert_eq!(sample_period_us(SampleMode::_8Adaptive), Some(125_000));
+        assert_eq!(accumulation_rate(SampleMode::_8Adaptive), Some(1024));
+        assert_eq!(accumulation_rate(SampleMode::_64), Some(64));
+    }
+
+    #[test]
+    fn every_mode() {
+        // 1024 SPS is a 976.5625 us cycle whatever the channels
+        for active in 1..=4 {
+            let t = Timing::new(SampleMode::_1024, active);
+            assert_eq!(t.period_ns, Some(976_563));
+            assert_eq!(
+                (t.sample_rate, t.accumulation_rate),
+                (Some(1024), Some(1024))
+            );
+            assert_eq!(t.ready_us, 1000);
+        }
+        let t = Timing::new(SampleMode::_8, 2);
+        assert_eq!(t.period_ns, Some(125_000_000));
+        assert_eq!((t.sample_rate, t.accumulation_rate), (Some(8), Some(8)));
+
+        // Adaptive modes sample at their rate and accumulate at 1024 SPS
+        for (mode, rate, period_ns) in [
+            (SampleMode::_1024Adaptive, 1024, 976_563),
+            (SampleMode::_256Adaptive, 256, 3_906_250),
+            (SampleMode::_64Adaptive, 64, 15_625_000),
+            (SampleMode::_8Adaptive, 8, 125_000_000),
+        ] {
+            let t = Timing::new(mode, 4);
+            assert_eq!(t.period_ns, Some(period_ns));
+            assert_eq!(
+                (t.sample_rate, t.accumulation_rate),
+                (Some(rate), Some(1024))
+            );
+        }
+
+        // Fast and Burst modes: 1024 SPS per channel with four channels, 4096 SPS with one
+        for (active, rate, period_ns) in [
+            (4, 1024, 976_563),
+            (3, 1365, 732_422),
+            (2, 2048, 488_281),
+            (1, 4096, 244_141),
+        ] {
+            for mode in [SampleMode::Fast, SampleMode::Burst] {
+                let t = Timing::new(mode, active);
+                assert_eq!(t.period_ns, Some(period_ns));
+                assert_eq!(
+                    (t.sample_rate, t.accumulation_rate),
+                    (Some(rate), Some(rate))
+                );
+            }
+        }
+
+        // Single-shot modes convert once, or 8 times, per REFRESH
+        let t = Timing::new(SampleMode::SingleShot, 4);
+        assert_eq!((t.period_ns, t.sample_rate, t.ready_us), (None, None, 1000));
+        assert_eq!(Timing::new(SampleMode::SingleShot8X, 4).ready_us, 7813);
+        assert_eq!(Timing::new(SampleMode::SingleShot8X, 1).ready_us, 1954);
+
+        let sleep = Timing::new(SampleMode::Sleep, 4);
+        assert_eq!((sleep.sample_rate, sleep.accumulation_rate), (None, None));
+        let none = Timing::new(SampleMode::Fast, 0);
+        assert_eq!((none.period_ns, none.sample_rate), (None, None));
+    }
+
+    #[test]
+    fn active_channels() {
+        let off = Channels {
+            _1: false,
+            _2: true,
+            _3: false,
+            _4: true,
+        };
+        assert_eq!(Timing::with_channels(SampleMode::Fast, off, 4).active, 2);
+        // Channels the part doesn't have are never converted
+        assert_eq!(
+            Timing::with_channels(SampleMode::Fast, off, 1).sample_rate,
+            Some(4096)
+        );
+    }
+
+    #[test]
+    fn nearest_count() {
+        // 4 samples at 1024 SPS are 3906 us, 8 are 7813 us
+        assert_eq!(
+            debounce(5_000, 1024),
+            Debounce {
+                count: SampleCount::_4,
+                effective_us: 3906
+            }
+        );
+        assert_eq!(debounce(6_000, 1024).count, SampleCount::_8);
+        assert_eq!(debounce(0, 1024).count, SampleCount::_1);
+        assert_eq!(
+            debounce(10_000_000, 8),
+            Debounce {
+                count: SampleCount::_16,
+                effective_us: 2_000_000
+            }
+        );
+    }
+}